@@ -0,0 +1,121 @@
+use crate::throttle::MAX_MUTE;
+use std::fmt::{self, Display, Formatter};
+
+/// Commands reserved to the bot owners, posted as `/admin <command>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// End what the bot is doing, e.g. the current game.
+    Stop,
+    /// Drop all state of the bot in this thread.
+    Reset,
+    /// Ignore posts other than admin commands.
+    Pause,
+    Resume,
+    Status,
+    /// Drop the commands of an identity for some minutes, by default the `THROTTLE` mute time,
+    /// for at most [`MAX_MUTE`].
+    Mute {
+        identity: String,
+        minutes: Option<u64>,
+    },
+    Unmute(String),
+}
+
+impl AdminCommand {
+    /// Parse an `/admin` post.
+    ///
+    /// Returns `None` if the post is not an admin command at all, and the unknown command as
+    /// error if it is not supported.
+    pub fn parse(content: &str) -> Option<Result<Self, String>> {
+        let mut tokens = content.split_whitespace();
+        if tokens.next() != Some("/admin") {
+            return None;
+        }
+        let command = match tokens.next() {
+            Some("stop") => AdminCommand::Stop,
+            Some("reset") => AdminCommand::Reset,
+            Some("pause") => AdminCommand::Pause,
+            Some("resume") => AdminCommand::Resume,
+            Some("status") => AdminCommand::Status,
+            Some("mute") => {
+                let identity = match tokens.next() {
+                    Some(identity) => identity.trim_start_matches('@').to_string(),
+                    None => return Some(Err(String::from("mute"))),
+                };
+                let minutes = match tokens.next().map(str::parse::<u64>) {
+                    Some(Ok(minutes)) if minutes <= MAX_MUTE.as_secs() / 60 => Some(minutes),
+                    Some(_) => return Some(Err(String::from("mute"))),
+                    None => None,
+                };
+                AdminCommand::Mute { identity, minutes }
+            }
+            Some("unmute") => match tokens.next() {
+                Some(identity) => {
+                    AdminCommand::Unmute(identity.trim_start_matches('@').to_string())
+                }
+                None => return Some(Err(String::from("unmute"))),
+            },
+            Some(command) => return Some(Err(command.to_string())),
+            None => return Some(Err(String::new())),
+        };
+        Some(Ok(command))
+    }
+}
+
+impl Display for AdminCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminCommand::Stop => write!(f, "/admin stop"),
+            AdminCommand::Reset => write!(f, "/admin reset"),
+            AdminCommand::Pause => write!(f, "/admin pause"),
+            AdminCommand::Resume => write!(f, "/admin resume"),
+            AdminCommand::Status => write!(f, "/admin status"),
+            AdminCommand::Mute {
+                identity,
+                minutes: Some(minutes),
+            } => write!(f, "/admin mute {} {}", identity, minutes),
+            AdminCommand::Mute {
+                identity,
+                minutes: None,
+            } => write!(f, "/admin mute {}", identity),
+            AdminCommand::Unmute(identity) => write!(f, "/admin unmute {}", identity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mute() {
+        assert_eq!(
+            AdminCommand::parse("/admin mute @Alice 30"),
+            Some(Ok(AdminCommand::Mute {
+                identity: String::from("Alice"),
+                minutes: Some(30),
+            }))
+        );
+        assert_eq!(
+            AdminCommand::parse("/admin mute Alice"),
+            Some(Ok(AdminCommand::Mute {
+                identity: String::from("Alice"),
+                minutes: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_mute_refuses_too_long() {
+        let longest = MAX_MUTE.as_secs() / 60;
+        assert!(
+            AdminCommand::parse(&format!("/admin mute Alice {}", longest))
+                .unwrap()
+                .is_ok()
+        );
+        for minutes in [longest + 1, u64::MAX] {
+            let post = format!("/admin mute Alice {}", minutes);
+            assert_eq!(AdminCommand::parse(&post), Some(Err(String::from("mute"))));
+        }
+    }
+}
@@ -0,0 +1,196 @@
+#[macro_use]
+extern crate log;
+
+use bots::reload::ConfigWatcher;
+use bots::schedule::{self, Schedule};
+use bots::settings::{CheckinAccount, ConfigArgs, Need, Settings};
+use bots::shutdown;
+use chrono::DateTime;
+use chrono_tz::Tz;
+use clap::Parser;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::sleep;
+
+const NEEDS: [Need; 1] = [Need::Checkin];
+// delay before retrying a failed check-in, multiplied by the number of attempts so far
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Check in every account listed in `CHECKIN.ACCOUNTS` every day, recording their fish
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Check in all accounts right away and exit
+    #[arg(long)]
+    once: bool,
+}
+
+/// Next check-in time after now, with up to `JITTER_SECS` added at random.
+fn next_run(settings: &Settings) -> Option<DateTime<Tz>> {
+    // the schedule was checked when loading the settings
+    let schedule: Schedule = settings.checkin.schedule.parse().ok()?;
+    let next = schedule.after(&schedule::now())?;
+    let jitter = rand::thread_rng().gen_range(0..=settings.checkin.jitter_secs);
+    Some(next + chrono::Duration::seconds(jitter as i64))
+}
+
+async fn try_checkin(settings: &Settings, token: String) -> Result<i64, String> {
+    let mut client = ykst_client::Client::new(
+        settings.api_url.clone(),
+        token,
+        settings.identity_code.clone(),
+        Some(20),
+    )
+    .await
+    .map_err(|err| err.to_string())?;
+    let fish = client.checkin().await.map_err(|err| err.to_string())?;
+    Ok(fish.fish)
+}
+
+/// Check in `account`, retrying up to `CHECKIN.ATTEMPTS` times; returns its fish balance.
+async fn checkin(settings: &Settings, account: &CheckinAccount) -> Result<i64, String> {
+    let token = account
+        .treehole_token
+        .clone()
+        .unwrap_or_else(|| settings.treehole_token.clone());
+    let mut attempt = 1;
+    loop {
+        match try_checkin(settings, token.clone()).await {
+            Ok(fish) => return Ok(fish),
+            Err(err) if attempt >= settings.checkin.attempts => return Err(err),
+            Err(err) => warn!("check in {}, attempt {}: {}", account.name, attempt, err),
+        }
+        sleep(RETRY_DELAY * attempt).await;
+        attempt += 1;
+    }
+}
+
+/// Latest balance of every account found in the history file.
+fn read_balances(path: &Path) -> HashMap<String, i64> {
+    let mut balances = HashMap::new();
+    let mut reader = match File::open(path) {
+        Ok(file) => csv::Reader::from_reader(file),
+        Err(_) => return balances,
+    };
+    for record in reader.records().flatten() {
+        if let (Some(account), Some(Ok(fish))) =
+            (record.get(1), record.get(2).map(str::parse::<i64>))
+        {
+            balances.insert(account.to_string(), fish);
+        }
+    }
+    balances
+}
+
+fn append_history(
+    path: &Path,
+    time: &DateTime<Tz>,
+    results: &[(String, Result<i64, String>)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let empty = file.metadata()?.len() == 0;
+    let mut writer = csv::Writer::from_writer(file);
+    if empty {
+        writer.write_record(["time", "account", "fish", "error"])?;
+    }
+    for (account, result) in results {
+        let (fish, error) = match result {
+            Ok(fish) => (fish.to_string(), String::new()),
+            Err(err) => (String::new(), err.clone()),
+        };
+        writer.write_record([time.to_rfc3339().as_str(), account, &fish, &error])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Check in every account, record the results and print a summary.
+async fn run(settings: &Settings, balances: &mut HashMap<String, i64>) {
+    let time = schedule::now();
+    let mut results = vec![];
+    for account in &settings.checkin.accounts {
+        info!("check in {}", account.name);
+        let result = checkin(settings, account).await;
+        if let Err(err) = &result {
+            error!("check in {}: {}", account.name, err);
+        }
+        results.push((account.name.clone(), result));
+    }
+    if let Err(err) = append_history(&settings.checkin.history_file, &time, &results) {
+        error!("{}: {}", settings.checkin.history_file.display(), err);
+    }
+
+    let succeeded = results.iter().filter(|(_, result)| result.is_ok()).count();
+    println!(
+        "{} check-in: {}/{} succeeded",
+        time.format("%Y-%m-%d %H:%M:%S"),
+        succeeded,
+        results.len()
+    );
+    for (account, result) in results {
+        match result {
+            Ok(fish) => {
+                let change = match balances.insert(account.clone(), fish) {
+                    Some(previous) => format!(" ({:+})", fish - previous),
+                    None => String::new(),
+                };
+                println!("  {}: {} fish{}", account, fish, change);
+            }
+            Err(err) => println!("  {}: failed, {}", account, err),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+    info!("read settings");
+    let mut settings = args.config.load(&NEEDS);
+    let mut balances = read_balances(&settings.checkin.history_file);
+
+    if args.once {
+        run(&settings, &mut balances).await;
+        return Ok(());
+    }
+
+    let mut watcher = ConfigWatcher::new(args.config.path.clone());
+    let signal = shutdown::signal();
+    tokio::pin!(signal);
+    let mut next = next_run(&settings);
+    loop {
+        let due = match next {
+            Some(due) => due,
+            None => {
+                warn!("no check-in scheduled anymore");
+                break;
+            }
+        };
+        info!("next check-in at {}", due);
+        let delay = (due - schedule::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = sleep(delay) => {
+                run(&settings, &mut balances).await;
+                next = next_run(&settings);
+            }
+            _ = watcher.changed() => {
+                if let Some(new) = watcher.reload(&settings, &NEEDS) {
+                    if new.checkin.schedule != settings.checkin.schedule {
+                        next = next_run(&new);
+                    }
+                    settings = new;
+                }
+            }
+            res = &mut signal => {
+                res?;
+                break;
+            }
+        }
+    }
+    info!("bye");
+    Ok(())
+}
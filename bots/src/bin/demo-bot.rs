@@ -1,17 +1,26 @@
-use config::Config;
-use ykst_client;
+use bots::settings::{ConfigArgs, Need};
+use clap::Parser;
 use ykst_client::model::RateType;
 
+/// Exercise the treehole API with a test thread
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let settings = Config::builder()
-        .add_source(config::File::with_name("config.yaml"))
-        .build()?;
-    let api_url = settings.get_string("API_URL")?;
-    let token = settings.get_string("TREEHOLE_TOKEN")?;
-    let identity = settings.get_string("IDENTITY_CODE")?;
-
-    let mut client = ykst_client::Client::new(api_url, token, identity, Some(5)).await?;
+    let args = Args::parse();
+    let settings = args.config.load(&[Need::Client]);
+
+    let mut client = ykst_client::Client::new(
+        settings.api_url,
+        settings.treehole_token,
+        settings.identity_code,
+        Some(5),
+    )
+    .await?;
 
     // get profile (user info)
     let _user = client.get_profile().await?;
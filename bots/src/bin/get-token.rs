@@ -1,16 +1,22 @@
-use config::Config;
-use reqwest;
+use bots::settings::{ConfigArgs, Need};
+use clap::Parser;
 use std::env;
 use ykst_client::model::tree_hole_client::TreeHoleClient;
 use ykst_client::model::*;
 
+/// Log in with jAccount (cookies in `JACCOUNT_COOKIE`) and print a treehole token
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let settings = Config::builder()
-        .add_source(config::File::with_name("config.yaml"))
-        .build()?;
-    let api_url = settings.get_string("AUTH_API_URL")?;
-    let redirect_url = settings.get_string("AUTH_REDIRECT_URL")?;
+    let args = Args::parse();
+    let settings = args.config.load(&[Need::Auth]);
+    let api_url = settings.auth_api_url;
+    let redirect_url = settings.auth_redirect_url;
 
     let mut client = TreeHoleClient::connect(api_url).await?;
 
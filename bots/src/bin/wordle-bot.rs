@@ -1,328 +1,82 @@
 #[macro_use]
 extern crate log;
 
-use cl_wordle::game::Game;
-use config::Config;
-use rand::Rng;
-use std::error::Error;
-use std::fmt::{Display, Formatter, Write};
-use std::str::FromStr;
-use std::thread::sleep;
-use std::{fmt, time};
-use ykst_client;
-
-enum Action {
-    Nop,
-    Start,
-    Guess(String),
-}
-
-#[derive(Debug, Clone)]
-enum ParseActionError {
-    InvalidWordError(String),
-    EmptyWordError,
-    UnsupportedActionError(String),
-}
-
-// https://fettblog.eu/rust-enums-wrapping-errors/
-impl Display for ParseActionError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match self {
-            ParseActionError::InvalidWordError(word) => {
-                write!(f, "❌  `{}` 为无效词汇，请确保单词为5个英文字母组成", word)
-            }
-            ParseActionError::EmptyWordError => {
-                write!(f, "❌  猜测单词为空，请输入5个字母组成的英文单词")
-            }
-            ParseActionError::UnsupportedActionError(action) => write!(
-                f,
-                "❌  `{}` 为不支持的动作，请输入`/start`或`/guess guess`",
-                action
-            ),
-        }
-    }
-}
-
-impl Error for ParseActionError {}
-
-impl Display for Action {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Action::Nop => write!(f, "nop"),
-            Action::Guess(guess) => write!(f, "/guess {}", guess),
-            Action::Start => write!(f, "/start"),
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Alphabet(pub [cl_wordle::Match; 26]);
-
-impl Display for Alphabet {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut res: fmt::Result = Ok(());
-        for (i, m) in self.0.iter().enumerate() {
-            if i % 7 == 0 {
-                res = write!(f, "\n\n");
-                if res.is_err() {
-                    return res;
-                }
-            }
-            let ch = (i as u8 + 'A' as u8) as char;
-            match m {
-                cl_wordle::Match::Wrong => res = write!(f, "~~{}~~ ", ch),
-                cl_wordle::Match::Close => res = write!(f, "{} ", ch),
-                cl_wordle::Match::Exact => res = write!(f, "***{}*** ", ch),
-            }
-            if res.is_err() {
-                return res;
-            }
-        }
-        res
-    }
-}
-
-struct Wordle {
-    game: Game,
-    feedbacks: Vec<String>,
-    alphabet: Alphabet,
-}
-
-impl Wordle {
-    fn new() -> Self {
-        let game = Game::from_day(rand::thread_rng().gen(), cl_wordle::words::NYTIMES);
-        Wordle {
-            game,
-            feedbacks: vec![],
-            alphabet: Alphabet {
-                0: [cl_wordle::Match::Close; 26],
-            },
-        }
-    }
+use bots::reload::ConfigWatcher;
+use bots::runner::{Options, Runner};
+use bots::settings::{ConfigArgs, Need, Settings};
+use bots::shutdown;
+use bots::wordle::WordleBot;
+use clap::Parser;
+use tokio::sync::watch;
+
+const NEEDS: [Need; 3] = [Need::Client, Need::Threads, Need::Wordle];
+
+/// Play Wordle in the threads listed in `THREAD_IDS`
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
 }
 
-// https://qubyte.codes/blog/parsing-input-from-stdin-to-structures-in-rust
-impl FromStr for Action {
-    type Err = ParseActionError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let iter = s.trim().split_whitespace(); // trim spaces and split by spaces
-        let tokens = iter.collect::<Vec<&str>>();
-        let action: Action;
-        if tokens[0].starts_with("/") {
-            match tokens[0] {
-                "/start" => action = Action::Start,
-                "/guess" => {
-                    if tokens.len() > 1 {
-                        let guess = tokens[1];
-                        if !(guess.len() == 5 && guess.chars().all(char::is_alphabetic)) {
-                            return Err(ParseActionError::InvalidWordError(guess.to_string()));
-                        }
-                        action = Action::Guess(guess.to_lowercase().to_string());
-                    } else {
-                        return Err(ParseActionError::EmptyWordError);
-                    }
-                }
-                _ => {
-                    return Err(ParseActionError::UnsupportedActionError(
-                        tokens[0].to_string(),
-                    ));
-                }
-            }
-        } else {
-            action = Action::Nop
-        }
-        Ok(action)
+fn options(settings: &Settings) -> Options {
+    Options {
+        identity: settings.identity_code.clone(),
+        bot_identities: settings.bot_identities.clone(),
+        offline_notice: settings.offline_notice.clone(),
+        owners: settings.owner_identities.clone(),
+        limits: settings.throttle.clone(),
+        batch_errors: settings.batch_errors,
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+    let args = Args::parse();
     info!("read settings");
-    let settings = Config::builder()
-        .add_source(config::File::with_name("config.yaml"))
-        .build()?;
-    let api_url = settings.get_string("API_URL")?;
-    let token = settings.get_string("TREEHOLE_TOKEN")?;
-    let identity = settings.get_string("IDENTITY_CODE")?;
-    let thread_id: u64 = settings.get_string("THREAD_ID")?.parse()?;
+    let mut settings = args.config.load(&NEEDS);
 
     info!("connect to treehole");
-    let mut client = ykst_client::Client::new(api_url, token, identity, Some(20)).await?;
-
-    let mut wordle: Option<Wordle> = None;
-
-    let thread = client.get_thread(thread_id).await?;
-    let mut floor = thread.reply_count;
-    info!("thread floor: {}", floor);
+    let client = ykst_client::Client::new(
+        settings.api_url.clone(),
+        settings.treehole_token.clone(),
+        settings.identity_code.clone(),
+        Some(20),
+    )
+    .await?;
+
+    // every thread plays its own game, with the settings current when it starts or is reset
+    let (handler_settings, current) = watch::channel(settings.clone());
+    let mut runner = Runner::new(client.clone(), options(&settings), move |thread_id| {
+        WordleBot::new(thread_id, &current.borrow())
+    });
+    runner.set_threads(&settings.thread_ids);
 
     info!("start loop");
+    let mut watcher = ConfigWatcher::new(args.config.path.clone());
+    let signal = shutdown::signal();
+    tokio::pin!(signal);
     loop {
-        sleep(time::Duration::from_secs(2));
-        let replies;
-        // info!("get threads replies");
-        match client.get_thread_replies(thread_id, floor, 19).await {
-            Ok(res) => replies = res,
-            Err(err) => {
-                error!("get_thread_replies: {}", err);
-                sleep(time::Duration::from_secs(8));
-                continue;
-                // return Err(err);
+        tokio::select! {
+            _ = watcher.changed() => {}
+            res = &mut signal => {
+                res?;
+                break;
             }
         }
-        for post in replies.posts {
-            floor = post.floor; // update post floor
-            let content = post.content.as_str();
-            // println!("{} {}", floor, content);
-            let post_id: u64;
-            if let Some(model) = &post.model {
-                post_id = model.id;
-            } else {
-                warn!("post.model is none");
-                continue;
-            }
-            let res = content.parse::<Action>();
-            if res.is_err() {
-                // failed to parse action
-                info!("failed to parse action");
-                if let Err(err) = client
-                    .reply_to_thread(thread_id, format!("{}", res.err().unwrap()))
-                    .await
-                {
-                    error!("reply_to_thread: {}", err);
-                    // return Err(err);
-                }
-                continue;
-            }
-            let action = res.unwrap();
-            info!("floor: {} action: {}", floor, action);
-            match action {
-                Action::Start => {
-                    if wordle.is_none() {
-                        // start game
-                        let w = Wordle::new();
-                        info!("game started, answer: {}", w.game.solution());
-                        wordle = Some(w);
-                        if let Err(err) = client.reply_to_thread(thread_id, String::from("🚀  Wordle 游戏开始，请输入`/guess guess`猜词，谜底为5位单词，一共6次机会，首先猜对的用户获胜。\n\n每次反馈都包括猜测的历史记录和字母表，历史记录的方格会显示三种颜色，表示猜测和答案的接近程度：\n\n+ 🟩代表该字母正确，对应字母***斜体加粗***\n\n+ 🟨代表谜底里有该字母但位置不对\n\n+ ⬛代表谜底没有该字母，对应字母~~删除~~\n\n字母表中***斜体加粗***代表谜底里有该字母，~~删除~~代表谜底没有该字母")).await {
-                            error!("reply_to_thread: {}", err);
-                            continue;
-                            // return Err(err);
-                        }
-                    } else {
-                        // game already started
-                        info!("game already started");
-                        if let Err(err) = client
-                            .reply_to_thread(
-                                thread_id,
-                                String::from("❌  游戏已经开始，请输入`/guess guess`猜词"),
-                            )
-                            .await
-                        {
-                            error!("reply_to_thread: {}", err);
-                            continue;
-                            // return Err(err);
-                        }
-                    }
-                }
-                Action::Guess(guess) => {
-                    if let Some(w) = wordle.as_mut() {
-                        let mut reply: String = String::new();
-                        // validate guess
-                        let result = w.game.guess(guess.as_str());
-                        if result.is_err() {
-                            info!("invalid guess");
-                            reply = format!(
-                                "❌  `{}` 为无效词汇，请确保单词为5个英文字母组成且有效",
-                                guess
-                            );
-                            if let Err(err) = client.reply_to_thread(thread_id, reply).await {
-                                error!("reply_to_thread: {}", err);
-                                continue;
-                                // return Err(err);
-                            }
-                            continue; // continue to avoid panic when calling game_over() when there's no guess
-                        } else {
-                            let matches = result.unwrap();
-                            let mut feedback = String::new();
-                            for (i, ch) in guess.chars().enumerate() {
-                                match &matches.0[i] {
-                                    cl_wordle::Match::Exact => {
-                                        write!(feedback, " ***{}***", ch)?;
-                                        w.alphabet.0[ch as usize - 'a' as usize] =
-                                            cl_wordle::Match::Exact;
-                                    }
-                                    cl_wordle::Match::Close => {
-                                        write!(feedback, " {}", ch)?;
-                                        w.alphabet.0[ch as usize - 'a' as usize] =
-                                            cl_wordle::Match::Exact;
-                                    }
-                                    cl_wordle::Match::Wrong => {
-                                        write!(feedback, " ~~{}~~", ch)?;
-                                        if w.alphabet.0[ch as usize - 'a' as usize]
-                                            == cl_wordle::Match::Close
-                                        {
-                                            // When the answer is leant, and the guess is erase, the first e is Close and second `e` is Wrong
-                                            w.alphabet.0[ch as usize - 'a' as usize] =
-                                                cl_wordle::Match::Wrong;
-                                        }
-                                    }
-                                }
-                            }
-                            write!(feedback, "    @{}", post.identity_code)?;
-                            w.feedbacks.push(feedback); // add feedback to feedbacks
-                                                        // show all history guesses
-                            for (i, gu) in w.game.guesses().enumerate() {
-                                write!(reply, "\n\n{} {}", gu.1, w.feedbacks[i])?;
-                            }
-                        }
-                        if let Some(end) = w.game.game_over() {
-                            reply = format!(
-                                "## {} {}/6{}",
-                                w.game.solution(),
-                                w.feedbacks.len(),
-                                reply
-                            );
-                            if end.is_win() {
-                                info!("game ends, win");
-                                write!(reply, "\n\n 恭喜{}，小鱼干奉上🎉", post.identity_code)?;
-                                if let Err(err) = client.appreciate_post(post_id, 1).await {
-                                    error!("appreciate_post: {}", err);
-                                    continue;
-                                    // return Err(err);
-                                }
-                            } else {
-                                info!("game ends, lose");
-                                write!(reply, "\n\n 游戏结束，再接再厉💪")?;
-                            }
-                            wordle = None;
-                        } else {
-                            // print alphabet
-                            write!(reply, "\n\n___\n\n {}", w.alphabet)?;
-                        }
-                        if let Err(err) = client.reply_to_thread(thread_id, reply).await {
-                            error!("reply_to_thread: {}", err);
-                            continue;
-                            // return Err(err);
-                        }
-                    } else {
-                        // game not started
-                        info!("game not started");
-                        if let Err(err) = client
-                            .reply_to_thread(
-                                thread_id,
-                                String::from("❌  游戏还未开始，请回复`/start`以开始游戏"),
-                            )
-                            .await
-                        {
-                            error!("reply_to_thread: {}", err);
-                            continue;
-                            // return Err(err);
-                        }
-                    }
-                }
-                _ => {}
+        if let Some(new) = watcher.reload(&settings, &NEEDS) {
+            if new.treehole_token != settings.treehole_token {
+                client.set_token(new.treehole_token.clone());
             }
+            handler_settings.send_replace(new.clone());
+            runner.set_options(options(&new));
+            runner.set_threads(&new.thread_ids);
+            settings = new;
         }
     }
+
+    info!("shutting down");
+    runner.shutdown().await;
+    info!("bye");
+    Ok(())
 }
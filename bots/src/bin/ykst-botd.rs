@@ -0,0 +1,142 @@
+#[macro_use]
+extern crate log;
+
+use bots::bot::Handler;
+use bots::reload::ConfigWatcher;
+use bots::runner::{Options, Runner};
+use bots::settings::{BotSettings, ConfigArgs, Need, Settings};
+use bots::shutdown;
+use clap::Parser;
+use tokio::sync::watch;
+use ykst_client::AuthClient;
+
+const NEEDS: [Need; 3] = [Need::Client, Need::Bots, Need::Wordle];
+
+/// Run all bots configured in `BOTS` with one treehole client
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+/// Options of the bot configured by `bot`.
+fn options(settings: &Settings, bot: &BotSettings) -> Options {
+    // bots never react to each other
+    let mut bot_identities = settings.bot_identities.clone();
+    bot_identities.push(settings.identity_code.clone());
+    bot_identities.extend(
+        settings
+            .bots
+            .iter()
+            .filter_map(|bot| bot.identity_code.clone()),
+    );
+    Options {
+        identity: bot
+            .identity_code
+            .clone()
+            .unwrap_or_else(|| settings.identity_code.clone()),
+        bot_identities,
+        offline_notice: settings.offline_notice.clone(),
+        owners: settings.owner_identities.clone(),
+        limits: settings.throttle.clone(),
+        batch_errors: bot.batch_errors.unwrap_or(settings.batch_errors),
+    }
+}
+
+/// Start the bot configured by `bot`, whose handlers are created with the settings `current`
+/// holds when their thread starts or is reset.
+fn start(
+    client: &AuthClient,
+    settings: &Settings,
+    bot: &BotSettings,
+    current: &watch::Receiver<Settings>,
+) -> Runner<Box<dyn Handler>> {
+    info!("start {} bot in threads {:?}", bot.kind, bot.thread_ids);
+    let kind = bot.kind.clone();
+    let current = current.clone();
+    // the type was checked when loading the settings
+    let mut runner = Runner::new(client.clone(), options(settings, bot), move |thread_id| {
+        bots::new_handler(&kind, thread_id, &current.borrow()).unwrap()
+    });
+    runner.set_threads(&bot.thread_ids);
+    runner
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+    info!("read settings");
+    let mut settings = args.config.load(&NEEDS);
+
+    info!("connect to treehole");
+    let client = ykst_client::Client::new(
+        settings.api_url.clone(),
+        settings.treehole_token.clone(),
+        settings.identity_code.clone(),
+        Some(20),
+    )
+    .await?;
+
+    let (handler_settings, current) = watch::channel(settings.clone());
+    let mut runners: Vec<_> = settings
+        .bots
+        .iter()
+        .map(|bot| start(&client, &settings, bot, &current))
+        .collect();
+
+    let mut watcher = ConfigWatcher::new(args.config.path.clone());
+    let signal = shutdown::signal();
+    tokio::pin!(signal);
+    loop {
+        tokio::select! {
+            _ = watcher.changed() => {}
+            res = &mut signal => {
+                res?;
+                break;
+            }
+        }
+        let new = match watcher.reload(&settings, &NEEDS) {
+            Some(new) => new,
+            None => continue,
+        };
+        if new.treehole_token != settings.treehole_token {
+            client.set_token(new.treehole_token.clone());
+        }
+        handler_settings.send_replace(new.clone());
+        // bots are matched by their position in `BOTS`, a bot changing type starts over
+        let mut old_runners = runners.into_iter();
+        runners = vec![];
+        for (i, bot) in new.bots.iter().enumerate() {
+            let runner = old_runners.next();
+            match (runner, settings.bots.get(i)) {
+                (Some(mut runner), Some(old)) if old.kind == bot.kind => {
+                    runner.set_options(options(&new, bot));
+                    runner.set_threads(&bot.thread_ids);
+                    runners.push(runner);
+                }
+                (runner, _) => {
+                    if let Some(runner) = runner {
+                        tokio::spawn(runner.shutdown());
+                    }
+                    runners.push(start(&client, &new, bot, &current));
+                }
+            }
+        }
+        for runner in old_runners {
+            tokio::spawn(runner.shutdown());
+        }
+        settings = new;
+    }
+
+    info!("shutting down");
+    let tasks: Vec<_> = runners
+        .into_iter()
+        .map(|runner| tokio::spawn(runner.shutdown()))
+        .collect();
+    for task in tasks {
+        task.await?;
+    }
+    info!("bye");
+    Ok(())
+}
@@ -0,0 +1,484 @@
+use bots::settings::{ConfigArgs, Need};
+use clap::Parser;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap};
+use ratatui::{Frame, Terminal};
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+use ykst_client::model::{Post, RateType, Thread};
+use ykst_client::{AuthClient, Category};
+
+// threads fetched per category
+const THREADS: u32 = 30;
+// posts fetched per page, the next page is fetched when scrolling past the last post
+const POSTS: u32 = 19;
+// how often a followed thread is checked for new replies
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Browse the treehole in the terminal
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+enum Screen {
+    Threads,
+    Thread(Box<ThreadView>),
+}
+
+struct ThreadView {
+    thread: Thread,
+    posts: Vec<Post>,
+    selected: ListState,
+    // last floor fetched
+    floor: u64,
+    // whether the last page fetched was the end of the thread
+    end: bool,
+    follow: bool,
+}
+
+/// Text being typed, and the post it replies to.
+struct Compose {
+    text: String,
+    post: Option<(u64, u64)>,
+}
+
+struct App {
+    client: AuthClient,
+    category: usize,
+    threads: Vec<Thread>,
+    selected: ListState,
+    screen: Screen,
+    compose: Option<Compose>,
+    status: String,
+    quit: bool,
+}
+
+fn post_id(post: &Post) -> u64 {
+    post.model.as_ref().map_or(0, |model| model.id)
+}
+
+fn thread_id(thread: &Thread) -> u64 {
+    thread.model.as_ref().map_or(0, |model| model.id)
+}
+
+impl App {
+    async fn load_threads(&mut self) {
+        let category = Category::ALL[self.category];
+        match self.client.get_threads(category, 0, THREADS).await {
+            Ok(response) => {
+                self.threads = response.threads;
+                self.selected
+                    .select((!self.threads.is_empty()).then_some(0));
+                self.status = format!("{} threads in {}", self.threads.len(), category.name());
+            }
+            Err(err) => self.status = format!("get_threads: {}", err),
+        }
+    }
+
+    async fn open_thread(&mut self, id: u64) {
+        let thread = match self.client.get_thread(id).await {
+            Ok(thread) => thread,
+            Err(err) => {
+                self.status = format!("get_thread {}: {}", id, err);
+                return;
+            }
+        };
+        let mut view = ThreadView {
+            thread,
+            posts: vec![],
+            selected: ListState::default(),
+            floor: 0,
+            end: false,
+            follow: false,
+        };
+        self.fetch_page(&mut view).await;
+        view.selected.select((!view.posts.is_empty()).then_some(0));
+        self.status = format!("thread #{}: {} replies", id, view.thread.reply_count);
+        self.screen = Screen::Thread(Box::new(view));
+    }
+
+    /// Fetch the page of posts of `view` after the last floor fetched, returning how many.
+    async fn fetch_page(&mut self, view: &mut ThreadView) -> usize {
+        let id = thread_id(&view.thread);
+        let posts = match self.client.get_thread_replies(id, view.floor, POSTS).await {
+            Ok(response) => response.posts,
+            Err(err) => {
+                self.status = format!("get_thread_replies {}: {}", id, err);
+                return 0;
+            }
+        };
+        view.end = posts.len() < POSTS as usize;
+        let count = posts.len();
+        for post in posts {
+            view.floor = view.floor.max(post.floor);
+            view.posts.push(post);
+        }
+        count
+    }
+
+    /// Move the selected post by `step`, fetching the next page past the last post fetched.
+    async fn scroll(&mut self, step: isize) {
+        let mut view = match std::mem::replace(&mut self.screen, Screen::Threads) {
+            Screen::Thread(view) => view,
+            screen => {
+                self.screen = screen;
+                return;
+            }
+        };
+        let last = view.selected.selected().map(|i| i + 1) == Some(view.posts.len());
+        if step > 0 && last && !view.end {
+            self.fetch_page(&mut view).await;
+        }
+        select(&mut view.selected, view.posts.len(), step);
+        self.screen = Screen::Thread(view);
+    }
+
+    async fn follow(&mut self) {
+        let mut view = match std::mem::replace(&mut self.screen, Screen::Threads) {
+            Screen::Thread(view) => view,
+            screen => {
+                self.screen = screen;
+                return;
+            }
+        };
+        if view.follow {
+            // stay at the bottom if already there
+            let at_bottom = view.selected.selected().map(|i| i + 1) == Some(view.posts.len());
+            let new = self.fetch_page(&mut view).await;
+            if new > 0 {
+                self.status = format!("{} new replies", new);
+                if at_bottom || view.selected.selected().is_none() {
+                    view.selected.select(view.posts.len().checked_sub(1));
+                }
+            }
+        }
+        self.screen = Screen::Thread(view);
+    }
+
+    async fn send(&mut self, compose: Compose) {
+        let view = match &self.screen {
+            Screen::Thread(view) => view,
+            Screen::Threads => return,
+        };
+        let id = thread_id(&view.thread);
+        let to = compose.post.map(|(id, _)| id);
+        match self.client.reply_to_post(id, to, compose.text).await {
+            Ok(post) => self.status = format!("replied at floor {}", post.floor),
+            Err(err) => self.status = format!("reply: {}", err),
+        }
+        // show the reply right away if the end of the thread is shown
+        if let Screen::Thread(mut view) = std::mem::replace(&mut self.screen, Screen::Threads) {
+            if view.end {
+                self.fetch_page(&mut view).await;
+                view.selected.select(view.posts.len().checked_sub(1));
+            }
+            self.screen = Screen::Thread(view);
+        }
+    }
+
+    async fn rate(&mut self, rate_type: RateType) {
+        let view = match &mut self.screen {
+            Screen::Thread(view) => view,
+            Screen::Threads => return,
+        };
+        let name = match rate_type {
+            RateType::Like => "liked",
+            _ => "disliked",
+        };
+        match view.selected.selected().and_then(|i| view.posts.get_mut(i)) {
+            Some(post) => match self.client.rate_post(post_id(post), rate_type).await {
+                Ok(rated) => {
+                    self.status = format!("{} floor {}", name, post.floor);
+                    *post = rated;
+                }
+                Err(err) => self.status = format!("rate_post: {}", err),
+            },
+            None => match self
+                .client
+                .rate_thread(thread_id(&view.thread), rate_type)
+                .await
+            {
+                Ok(thread) => {
+                    self.status = format!("{} the thread", name);
+                    view.thread = thread;
+                }
+                Err(err) => self.status = format!("rate_thread: {}", err),
+            },
+        }
+    }
+
+    async fn on_key(&mut self, key: KeyEvent) {
+        if let Some(compose) = &mut self.compose {
+            match key.code {
+                KeyCode::Esc => {
+                    self.compose = None;
+                    self.status = String::from("reply cancelled");
+                }
+                KeyCode::Enter if !compose.text.trim().is_empty() => {
+                    if let Some(compose) = self.compose.take() {
+                        self.send(compose).await;
+                    }
+                }
+                KeyCode::Backspace => {
+                    compose.text.pop();
+                }
+                KeyCode::Char(ch) => compose.text.push(ch),
+                _ => {}
+            }
+            return;
+        }
+
+        match &mut self.screen {
+            Screen::Threads => match key.code {
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Left | KeyCode::BackTab => {
+                    self.category = (self.category + Category::ALL.len() - 1) % Category::ALL.len();
+                    self.load_threads().await;
+                }
+                KeyCode::Right | KeyCode::Tab => {
+                    self.category = (self.category + 1) % Category::ALL.len();
+                    self.load_threads().await;
+                }
+                KeyCode::Up => select(&mut self.selected, self.threads.len(), -1),
+                KeyCode::Down => select(&mut self.selected, self.threads.len(), 1),
+                KeyCode::Char('r') => self.load_threads().await,
+                KeyCode::Enter => {
+                    if let Some(thread) = self.selected.selected().and_then(|i| self.threads.get(i))
+                    {
+                        self.open_thread(thread_id(thread)).await;
+                    }
+                }
+                _ => {}
+            },
+            Screen::Thread(view) => match key.code {
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Esc | KeyCode::Backspace => {
+                    self.screen = Screen::Threads;
+                    self.status.clear();
+                }
+                KeyCode::Up => self.scroll(-1).await,
+                KeyCode::Down => self.scroll(1).await,
+                KeyCode::Home => view.selected.select(None),
+                KeyCode::Char('f') => {
+                    view.follow = !view.follow;
+                    self.status = format!("follow {}", if view.follow { "on" } else { "off" });
+                }
+                KeyCode::Char('r') => {
+                    self.compose = Some(Compose {
+                        text: String::new(),
+                        post: None,
+                    })
+                }
+                KeyCode::Char('R') => {
+                    let post = view.selected.selected().and_then(|i| view.posts.get(i));
+                    self.compose = Some(Compose {
+                        text: String::new(),
+                        post: post.map(|post| (post_id(post), post.floor)),
+                    })
+                }
+                KeyCode::Char('l') => self.rate(RateType::Like).await,
+                KeyCode::Char('d') => self.rate(RateType::Dislike).await,
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Move the selection of a list of `len` items by `step`.
+fn select(state: &mut ListState, len: usize, step: isize) {
+    if len == 0 {
+        return;
+    }
+    let i = match state.selected() {
+        Some(i) => (i as isize + step).clamp(0, len as isize - 1) as usize,
+        None => 0,
+    };
+    state.select(Some(i));
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        .split(frame.size());
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    match &mut app.screen {
+        Screen::Threads => {
+            let titles: Vec<_> = Category::ALL
+                .iter()
+                .map(|category| category.name())
+                .collect();
+            let tabs = Tabs::new(titles)
+                .select(app.category)
+                .highlight_style(highlight)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("categories ←/→"),
+                );
+            frame.render_widget(tabs, chunks[0]);
+
+            let items: Vec<_> = app
+                .threads
+                .iter()
+                .map(|thread| {
+                    ListItem::new(format!(
+                        "#{} {}  ({} replies, {}👍 {}👎)",
+                        thread_id(thread),
+                        thread.title,
+                        thread.reply_count,
+                        thread.like_count,
+                        thread.dislike_count
+                    ))
+                })
+                .collect();
+            let list = List::new(items)
+                .highlight_style(highlight)
+                .block(Block::default().borders(Borders::ALL).title("threads"));
+            frame.render_stateful_widget(list, chunks[1], &mut app.selected);
+        }
+        Screen::Thread(view) => {
+            let title = format!(
+                "#{} {}  ({}👍 {}👎){}",
+                thread_id(&view.thread),
+                view.thread.title,
+                view.thread.like_count,
+                view.thread.dislike_count,
+                if view.follow { "  [following]" } else { "" }
+            );
+            let header = Paragraph::new(view.thread.content.as_str())
+                .block(Block::default().borders(Borders::ALL).title(title));
+            frame.render_widget(header, chunks[0]);
+
+            let items: Vec<_> = view
+                .posts
+                .iter()
+                .map(|post| {
+                    let mut lines = vec![Line::styled(
+                        format!(
+                            "{}F {}{}  {}👍 {}👎",
+                            post.floor,
+                            post.identity_code,
+                            post.reply_to_post_id
+                                .map(|id| format!(" → #{}", id))
+                                .unwrap_or_default(),
+                            post.like_count,
+                            post.dislike_count
+                        ),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )];
+                    lines.extend(post.content.lines().map(|line| Line::raw(line.to_string())));
+                    lines.push(Line::raw(""));
+                    ListItem::new(Text::from(lines))
+                })
+                .collect();
+            let list = List::new(items)
+                .highlight_style(highlight)
+                .block(Block::default().borders(Borders::ALL).title("posts"));
+            frame.render_stateful_widget(list, chunks[1], &mut view.selected);
+        }
+    }
+
+    let footer = match &app.compose {
+        Some(compose) => {
+            let title = match compose.post {
+                Some((_, floor)) => format!("reply to {}F, Enter to send, Esc to cancel", floor),
+                None => String::from("reply, Enter to send, Esc to cancel"),
+            };
+            Paragraph::new(compose.text.as_str())
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(title))
+        }
+        None => {
+            let keys = match app.screen {
+                Screen::Threads => "↑/↓ select  Enter open  r refresh  q quit",
+                Screen::Thread(_) => {
+                    "↑/↓ select  Home thread  r reply  R reply to post  l like  d dislike  f follow  Esc back  q quit"
+                }
+            };
+            Paragraph::new(app.status.as_str())
+                .block(Block::default().borders(Borders::ALL).title(keys))
+        }
+    };
+    frame.render_widget(footer, chunks[2]);
+}
+
+fn restore() -> io::Result<()> {
+    disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    Ok(())
+}
+
+async fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app.load_threads().await;
+    let mut followed = Instant::now();
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, app))?;
+        if event::poll(Duration::from_millis(200))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.on_key(key).await;
+                }
+            }
+        }
+        if followed.elapsed() >= FOLLOW_INTERVAL {
+            app.follow().await;
+            followed = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let settings = args.config.load(&[Need::Client]);
+    let client = ykst_client::Client::new(
+        settings.api_url,
+        settings.treehole_token,
+        settings.identity_code,
+        Some(20),
+    )
+    .await?;
+
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    // leave the terminal usable if anything panics
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        hook(info);
+    }));
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let mut app = App {
+        client,
+        category: 0,
+        threads: vec![],
+        selected: ListState::default(),
+        screen: Screen::Threads,
+        compose: None,
+        status: String::new(),
+        quit: false,
+    };
+    let result = run(&mut terminal, &mut app).await;
+    restore()?;
+    result
+}
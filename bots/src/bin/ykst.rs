@@ -0,0 +1,323 @@
+use bots::export::{self, Format};
+use bots::json::{post_json, thread_json, user_json};
+use bots::settings::{ConfigArgs, Need};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use serde_json::{json, Value};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use ykst_client::model::{Post, RateType, Tag, Thread};
+use ykst_client::Category;
+
+/// Use the treehole from the command line
+#[derive(Parser)]
+#[command(name = "ykst", subcommand_required(false))]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    /// Required unless `--check-config` is given
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the profile of the account
+    Profile,
+    /// Threads of the account
+    #[command(subcommand)]
+    Threads(ThreadsCommand),
+    /// Show or create a thread
+    #[command(subcommand)]
+    Thread(ThreadCommand),
+    /// Reply to a thread, reading the content from stdin if not given
+    Reply {
+        thread_id: u64,
+        /// Post to reply to
+        #[arg(long = "to")]
+        post_id: Option<u64>,
+        content: Option<String>,
+    },
+    /// Like a thread or a post
+    Like(Target),
+    /// Dislike a thread or a post
+    Dislike(Target),
+    /// Give fish to a thread or a post
+    Appreciate {
+        #[command(flatten)]
+        target: Target,
+        #[arg(long, default_value_t = 1)]
+        amount: i32,
+    },
+    /// Check in for the daily fish
+    Checkin,
+    /// Archive all posts of a thread, resuming archives which already exist
+    Export {
+        thread_id: u64,
+        /// Archive to write, in the format given by its extension: jsonl, csv, md or html
+        #[arg(short, long = "output", required = true)]
+        outputs: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ThreadsCommand {
+    /// List the threads created by the account
+    List,
+}
+
+#[derive(Subcommand)]
+enum ThreadCommand {
+    /// Show a thread and a page of its posts
+    Show {
+        thread_id: u64,
+        /// Page of posts, starting at 1
+        #[arg(long, default_value_t = 1)]
+        page: u64,
+        /// Posts per page
+        #[arg(long, default_value_t = 20)]
+        size: u32,
+    },
+    /// Create a thread, reading the content from stdin if not given
+    Create {
+        /// Category name or id, e.g. `main` or `1`
+        #[arg(long, default_value = "main")]
+        category: Category,
+        #[arg(long)]
+        title: String,
+        /// Comma separated tags
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+        content: Option<String>,
+    },
+}
+
+#[derive(clap::Args)]
+struct Target {
+    /// Id of the thread, or of the post with `--post`
+    id: u64,
+    #[arg(long)]
+    post: bool,
+}
+
+/// `content`, or all of stdin if not given.
+fn content(content: Option<String>) -> io::Result<String> {
+    match content {
+        Some(content) => Ok(content),
+        None => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            Ok(content.trim_end().to_string())
+        }
+    }
+}
+
+fn print_thread(thread: &Thread) {
+    let id = thread.model.as_ref().map_or(0, |model| model.id);
+    let category = Category::from_id(thread.category_id).map_or("?", Category::name);
+    println!("#{} [{}] {}", id, category, thread.title);
+    if !thread.tags.is_empty() {
+        let tags: Vec<_> = thread.tags.iter().map(|tag| tag.name.as_str()).collect();
+        println!("tags: {}", tags.join(", "));
+    }
+    println!(
+        "by {}, {} replies, {} likes, {} dislikes",
+        thread.identity_code, thread.reply_count, thread.like_count, thread.dislike_count
+    );
+}
+
+fn print_post(post: &Post) {
+    let id = post.model.as_ref().map_or(0, |model| model.id);
+    match post.reply_to_post_id {
+        Some(to) => println!(
+            "--- {}F #{} {} (reply to #{})",
+            post.floor, id, post.identity_code, to
+        ),
+        None => println!("--- {}F #{} {}", post.floor, id, post.identity_code),
+    }
+    println!("{}", post.content);
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    if args.command.is_none() && !args.config.check_config {
+        Args::command()
+            .error(
+                ErrorKind::MissingSubcommand,
+                "a command is required unless --check-config is given",
+            )
+            .exit();
+    }
+    // exits after checking with `--check-config`
+    let settings = args.config.load(&[Need::Client]);
+    let command = args.command.expect("checked above");
+
+    let mut client = ykst_client::Client::new(
+        settings.api_url,
+        settings.treehole_token,
+        settings.identity_code,
+        Some(20),
+    )
+    .await?;
+
+    match command {
+        Command::Profile => {
+            let user = client.get_profile().await?;
+            if args.json {
+                println!("{}", user_json(&user));
+            } else {
+                let id = user.model.as_ref().map_or(0, |model| model.id);
+                println!("#{} {}", id, user.nickname);
+            }
+        }
+        Command::Threads(ThreadsCommand::List) => {
+            let threads = client.get_user_threads().await?.threads;
+            if args.json {
+                let threads: Vec<_> = threads.iter().map(thread_json).collect();
+                println!("{}", Value::Array(threads));
+            } else {
+                for thread in &threads {
+                    print_thread(thread);
+                    println!();
+                }
+            }
+        }
+        Command::Thread(ThreadCommand::Show {
+            thread_id,
+            page,
+            size,
+        }) => {
+            let thread = client.get_thread(thread_id).await?;
+            let last = page.saturating_sub(1) * size as u64;
+            let posts = client
+                .get_thread_replies(thread_id, last, size)
+                .await?
+                .posts;
+            if args.json {
+                let mut thread = thread_json(&thread);
+                thread["posts"] = posts.iter().map(post_json).collect();
+                println!("{}", thread);
+            } else {
+                print_thread(&thread);
+                println!("\n{}\n", thread.content);
+                for post in &posts {
+                    print_post(post);
+                }
+                let pages = thread.reply_count.div_ceil(size.max(1) as u64);
+                println!("--- page {}/{}", page, pages.max(1));
+            }
+        }
+        Command::Thread(ThreadCommand::Create {
+            category,
+            title,
+            tags,
+            content: text,
+        }) => {
+            let tags = tags
+                .into_iter()
+                .map(|name| Tag {
+                    name,
+                    ..Default::default()
+                })
+                .collect();
+            let thread = client
+                .create_thread(category, title, content(text)?, Some(tags))
+                .await?;
+            if args.json {
+                println!("{}", thread_json(&thread));
+            } else {
+                print_thread(&thread);
+            }
+        }
+        Command::Reply {
+            thread_id,
+            post_id,
+            content: text,
+        } => {
+            let post = client
+                .reply_to_post(thread_id, post_id, content(text)?)
+                .await?;
+            if args.json {
+                println!("{}", post_json(&post));
+            } else {
+                print_post(&post);
+            }
+        }
+        Command::Like(target) => rate(&mut client, &target, RateType::Like, args.json).await?,
+        Command::Dislike(target) => {
+            rate(&mut client, &target, RateType::Dislike, args.json).await?
+        }
+        Command::Appreciate { target, amount } => {
+            if target.post {
+                let post = client.appreciate_post(target.id, amount).await?;
+                print_result(&post_json(&post), args.json);
+            } else {
+                let thread = client.appreciate_thread(target.id, amount).await?;
+                print_result(&thread_json(&thread), args.json);
+            }
+        }
+        Command::Export { thread_id, outputs } => {
+            let mut archives = vec![];
+            for path in outputs {
+                match Format::from_path(&path) {
+                    Some(format) => archives.push((path, format)),
+                    None => {
+                        return Err(format!("{}: unknown archive format", path.display()).into())
+                    }
+                }
+            }
+            for (path, format) in archives {
+                let count = export::export(&mut client, thread_id, &path, format).await?;
+                if args.json {
+                    println!(
+                        "{}",
+                        json!({ "path": path, "format": format.to_string(), "posts": count })
+                    );
+                } else {
+                    println!("{}: {} new posts", path.display(), count);
+                }
+            }
+        }
+        Command::Checkin => {
+            let fish = client.checkin().await?;
+            if args.json {
+                println!("{}", json!({ "fish": fish.fish }));
+            } else {
+                println!("{} fish", fish.fish);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn rate(
+    client: &mut ykst_client::AuthClient,
+    target: &Target,
+    rate_type: RateType,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if target.post {
+        let post = client.rate_post(target.id, rate_type).await?;
+        print_result(&post_json(&post), json);
+    } else {
+        let thread = client.rate_thread(target.id, rate_type).await?;
+        print_result(&thread_json(&thread), json);
+    }
+    Ok(())
+}
+
+/// Print the rated or appreciated thread or post, in full with `--json`.
+fn print_result(value: &Value, json: bool) {
+    if json {
+        println!("{}", value);
+    } else {
+        println!(
+            "#{}: {} likes, {} dislikes",
+            value["id"], value["like_count"], value["dislike_count"]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::schedule::Schedule;
+use ykst_client::model::Post;
+
+/// Something a [`Handler`] wants the bot to do in its thread.
+#[derive(Debug, Clone)]
+pub enum Response {
+    /// Reply to the thread.
+    Reply(String),
+    /// Reply to the post `post_id`, e.g. to answer a player in their own game.
+    ReplyTo { post_id: u64, content: String },
+    /// Reply to an invalid command; errors of one polling batch may be posted together, see
+    /// [`crate::runner::Options::batch_errors`].
+    Error(String),
+    /// Appreciate a post with `amount` fish.
+    Appreciate { post_id: u64, amount: i32 },
+}
+
+/// Reacts to the posts of a single thread.
+///
+/// Every watched thread gets its own handler, so a handler only ever holds the state of its thread.
+pub trait Handler: Send + 'static {
+    fn handle(&mut self, post: &Post) -> Vec<Response>;
+
+    /// Called once when the bot stops watching the thread, to save state or say goodbye.
+    fn on_shutdown(&mut self) -> Vec<Response> {
+        vec![]
+    }
+
+    /// End what the bot is doing, on `/admin stop`.
+    fn stop(&mut self) -> Vec<Response> {
+        vec![]
+    }
+
+    /// Describe the state of the bot, for `/admin status`.
+    fn status(&self) -> String {
+        String::new()
+    }
+
+    /// Named schedules on which [`Handler::on_schedule`] is called, read when the handler is
+    /// created.
+    fn schedules(&self) -> Vec<(String, Schedule)> {
+        vec![]
+    }
+
+    /// Called between two polls of the thread when the schedule `name` is due.
+    fn on_schedule(&mut self, _name: &str) -> Vec<Response> {
+        vec![]
+    }
+}
+
+impl Handler for Box<dyn Handler> {
+    fn handle(&mut self, post: &Post) -> Vec<Response> {
+        (**self).handle(post)
+    }
+
+    fn on_shutdown(&mut self) -> Vec<Response> {
+        (**self).on_shutdown()
+    }
+
+    fn stop(&mut self) -> Vec<Response> {
+        (**self).stop()
+    }
+
+    fn status(&self) -> String {
+        (**self).status()
+    }
+
+    fn schedules(&self) -> Vec<(String, Schedule)> {
+        (**self).schedules()
+    }
+
+    fn on_schedule(&mut self, name: &str) -> Vec<Response> {
+        (**self).on_schedule(name)
+    }
+}
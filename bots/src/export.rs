@@ -0,0 +1,573 @@
+use crate::json::post_json;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use ykst_client::model::{Post, Thread};
+use ykst_client::AuthClient;
+
+// posts fetched per request
+const PAGE_SIZE: u32 = 19;
+// closes an HTML transcript, removed before appending to it
+const HTML_FOOTER: &str = "</main>\n</body>\n</html>\n";
+// opens every post of a transcript, followed by the floor
+const MARKDOWN_ANCHOR: &str = "<a id=\"floor-";
+const HTML_ANCHOR: &str = "<article id=\"floor-";
+
+/// File format of a thread archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON object per post.
+    JsonLines,
+    Csv,
+    /// Transcript of the thread, posts rendered as posted.
+    Markdown,
+    /// Transcript of the thread for the browser.
+    Html,
+}
+
+impl Format {
+    /// Guess the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" | "json-lines" => Ok(Format::JsonLines),
+            "csv" => Ok(Format::Csv),
+            "md" | "markdown" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            _ => Err(format!("unknown format `{}`", s)),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::JsonLines => write!(f, "jsonl"),
+            Format::Csv => write!(f, "csv"),
+            Format::Markdown => write!(f, "markdown"),
+            Format::Html => write!(f, "html"),
+        }
+    }
+}
+
+/// Append the posts of `thread_id` to the archive at `path`, returning how many were written.
+///
+/// An existing archive is resumed after the last floor it holds; a new one starts with the
+/// thread itself where the format allows.
+pub async fn export(
+    client: &mut AuthClient,
+    thread_id: u64,
+    path: &Path,
+    format: Format,
+) -> Result<usize, Box<dyn Error>> {
+    let (mut file, floor) = open(path, format)?;
+    if file.metadata()?.len() == 0 {
+        let thread = client.get_thread(thread_id).await?;
+        write_header(&mut file, &thread, format)?;
+    }
+    if floor > 0 {
+        info!("resume {} after floor {}", path.display(), floor);
+    }
+
+    let mut csv = csv::Writer::from_writer(file.try_clone()?);
+    let mut floor = floor;
+    let mut count = 0;
+    loop {
+        let posts = client
+            .get_thread_replies(thread_id, floor, PAGE_SIZE)
+            .await?
+            .posts;
+        if posts.is_empty() {
+            break;
+        }
+        for post in &posts {
+            floor = floor.max(post.floor);
+            match format {
+                Format::Csv => csv.write_record(csv_record(post))?,
+                _ => write_post(&mut file, post, format)?,
+            }
+        }
+        count += posts.len();
+        debug!("exported {} posts, floor {}", count, floor);
+    }
+    csv.flush()?;
+    if format == Format::Html {
+        file.write_all(HTML_FOOTER.as_bytes())?;
+    }
+    Ok(count)
+}
+
+const CSV_HEADER: [&str; 8] = [
+    "floor",
+    "id",
+    "created_at",
+    "identity_code",
+    "reply_to_post_id",
+    "like_count",
+    "dislike_count",
+    "content",
+];
+
+fn csv_record(post: &Post) -> Vec<String> {
+    let model = post.model.as_ref();
+    vec![
+        post.floor.to_string(),
+        model.map(|model| model.id.to_string()).unwrap_or_default(),
+        model
+            .and_then(|model| model.created_at.as_ref())
+            .map(|time| time.seconds.to_string())
+            .unwrap_or_default(),
+        post.identity_code.clone(),
+        post.reply_to_post_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        post.like_count.to_string(),
+        post.dislike_count.to_string(),
+        post.content.clone(),
+    ]
+}
+
+/// Open the archive at `path` for appending, returning it with the last floor it holds.
+///
+/// Whatever an interrupted export left of its last post is dropped, to be written again.
+fn open(path: &Path, format: Format) -> Result<(File, u64), Box<dyn Error>> {
+    if format == Format::Html && path.exists() {
+        // reopen the transcript, the footer is written again at the end
+        strip_html_footer(path)?;
+    }
+    let (floor, len) = last_floor(path, format)?;
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.set_len(len)?;
+    Ok((file, floor))
+}
+
+/// Last floor in the archive at `path`, 0 if there is none, and the length of the archive up to
+/// the end of that floor.
+///
+/// A Markdown transcript can't tell a post cut short, its last post is always left out.
+fn last_floor(path: &Path, format: Format) -> Result<(u64, u64), Box<dyn Error>> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(err) => return Err(err.into()),
+    };
+    let mut floor = 0;
+    // end of the last complete record
+    let mut end = 0;
+    match format {
+        Format::JsonLines => {
+            for line in lines(&content) {
+                let post = serde_json::from_slice::<serde_json::Value>(line.text);
+                // a line cut short by an interrupted export
+                if let (Ok(post), true) = (post, line.complete) {
+                    floor = floor.max(post["floor"].as_u64().unwrap_or(0));
+                    end = line.end;
+                }
+            }
+        }
+        Format::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(&content[..]);
+            let mut record = csv::StringRecord::new();
+            loop {
+                let start = reader.position().byte() as usize;
+                match reader.read_record(&mut record) {
+                    Ok(true) => {}
+                    Ok(false) | Err(_) => break,
+                }
+                let next = reader.position().byte() as usize;
+                let raw = &content[start..next];
+                // quotes come in pairs, a record cut short in a quoted field has an odd number
+                let quotes = raw.iter().filter(|&&byte| byte == b'"').count();
+                if record.len() != CSV_HEADER.len() || !raw.ends_with(b"\n") || quotes % 2 == 1 {
+                    break;
+                }
+                // the header has no number
+                let floor_field = record.get(0).and_then(|field| field.parse().ok());
+                floor = floor.max(floor_field.unwrap_or(0));
+                end = next;
+            }
+        }
+        Format::Markdown => {
+            // up to the last post, after the header
+            end = content.len();
+            let mut last = None;
+            for line in lines(&content) {
+                if let Some(found) = anchor(line.text, MARKDOWN_ANCHOR) {
+                    // every post starts with a rule before the anchor
+                    let start = line.start.saturating_sub("___\n\n".len());
+                    if let Some((previous, _)) = last.replace((found, start)) {
+                        floor = floor.max(previous);
+                    }
+                }
+            }
+            if let Some((_, start)) = last {
+                end = start;
+            }
+        }
+        Format::Html => {
+            // posts are escaped, only the transcript opens and closes articles
+            end = content.len();
+            let mut current = None;
+            for line in lines(&content) {
+                if let Some(found) = anchor(line.text, HTML_ANCHOR) {
+                    current = Some(found);
+                    end = line.start;
+                }
+                if line.text == b"</article>" && line.complete {
+                    if let Some(found) = current.take() {
+                        floor = floor.max(found);
+                    }
+                    end = line.end;
+                }
+            }
+        }
+    }
+    Ok((floor, end as u64))
+}
+
+// line of an archive, without its newline
+struct Line<'a> {
+    text: &'a [u8],
+    start: usize,
+    end: usize,
+    // ends with a newline
+    complete: bool,
+}
+
+fn lines(content: &[u8]) -> impl Iterator<Item = Line<'_>> {
+    let mut start = 0;
+    content
+        .split_inclusive(|&byte| byte == b'\n')
+        .map(move |line| {
+            let complete = line.ends_with(b"\n");
+            let text = if complete {
+                &line[..line.len() - 1]
+            } else {
+                line
+            };
+            let line = Line {
+                text,
+                start,
+                end: start + line.len(),
+                complete,
+            };
+            start = line.end;
+            line
+        })
+}
+
+/// Floor of a post opened by `line`, if it starts with `marker`.
+fn anchor(line: &[u8], marker: &str) -> Option<u64> {
+    let rest = line.strip_prefix(marker.as_bytes())?;
+    let number = rest.split(|&byte| byte == b'"').next()?;
+    std::str::from_utf8(number).ok()?.parse().ok()
+}
+
+fn write_header(file: &mut File, thread: &Thread, format: Format) -> Result<(), Box<dyn Error>> {
+    let id = thread.model.as_ref().map_or(0, |model| model.id);
+    match format {
+        Format::JsonLines => {}
+        Format::Csv => {
+            let mut csv = csv::Writer::from_writer(&mut *file);
+            csv.write_record(CSV_HEADER)?;
+            csv.flush()?;
+        }
+        Format::Markdown => write!(
+            file,
+            "# {}\n\n> #{} by {}\n\n{}\n\n",
+            thread.title,
+            id,
+            thread.identity_code,
+            escape_anchors(&thread.content)
+        )?,
+        Format::Html => write!(
+            file,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>article {{ border-top: 1px solid #ccc; }} .content {{ white-space: pre-wrap; }}</style>\n\
+             </head>\n<body>\n<main>\n<h1>{title}</h1>\n<p>#{id} by {identity}</p>\n\
+             <div class=\"content\">{content}</div>\n",
+            title = escape_html(&thread.title),
+            id = id,
+            identity = escape_html(&thread.identity_code),
+            content = escape_html(&thread.content),
+        )?,
+    }
+    Ok(())
+}
+
+fn write_post(file: &mut File, post: &Post, format: Format) -> Result<(), Box<dyn Error>> {
+    let id = post.model.as_ref().map_or(0, |model| model.id);
+    let reply_to = post
+        .reply_to_post_id
+        .map(|to| format!(" → #{}", to))
+        .unwrap_or_default();
+    match format {
+        Format::JsonLines => writeln!(file, "{}", post_json(post))?,
+        // written with the csv writer
+        Format::Csv => {}
+        Format::Markdown => write!(
+            file,
+            "___\n\n{anchor}{floor}\"></a>\n### {floor}F #{id} {identity}{reply_to}\n\n{content}\n\n",
+            anchor = MARKDOWN_ANCHOR,
+            floor = post.floor,
+            id = id,
+            identity = post.identity_code,
+            reply_to = reply_to,
+            content = escape_anchors(&post.content),
+        )?,
+        Format::Html => write!(
+            file,
+            "{anchor}{floor}\">\n<h3>{floor}F #{id} {identity}{reply_to}</h3>\n\
+             <div class=\"content\">{content}</div>\n</article>\n",
+            anchor = HTML_ANCHOR,
+            floor = post.floor,
+            id = id,
+            identity = escape_html(&post.identity_code),
+            reply_to = reply_to,
+            content = escape_html(&post.content),
+        )?,
+    }
+    Ok(())
+}
+
+/// Drop the footer of the HTML transcript at `path`, if it ends with it.
+fn strip_html_footer(path: &Path) -> Result<(), Box<dyn Error>> {
+    let content = fs::read(path)?;
+    if content.ends_with(HTML_FOOTER.as_bytes()) {
+        let file = OpenOptions::new().write(true).open(path)?;
+        file.set_len((content.len() - HTML_FOOTER.len()) as u64)?;
+    }
+    Ok(())
+}
+
+/// Escape the lines of `text` which would pass for the anchor of a post in a Markdown transcript.
+fn escape_anchors(text: &str) -> String {
+    let escaped = text.replace(
+        &format!("\n{}", MARKDOWN_ANCHOR),
+        &format!("\n\\{}", MARKDOWN_ANCHOR),
+    );
+    if escaped.starts_with(MARKDOWN_ANCHOR) {
+        format!("\\{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const FORMATS: [Format; 4] = [
+        Format::JsonLines,
+        Format::Csv,
+        Format::Markdown,
+        Format::Html,
+    ];
+
+    fn post(floor: u64) -> Post {
+        let content = match floor {
+            // would pass for another post of a Markdown transcript
+            4 => String::from("___\n\n<a id=\"floor-99\"></a>\n### 99F"),
+            _ => format!("post {}\nwith \"quotes\", commas\n\nand lines", floor),
+        };
+        Post {
+            floor,
+            content,
+            identity_code: String::from("Alice"),
+            ..Post::default()
+        }
+    }
+
+    // archive in the temporary directory, removed when dropped
+    struct Archive(PathBuf);
+
+    impl Archive {
+        fn new(name: &str, format: Format) -> Self {
+            let name = format!("export-{}-{}.{}", std::process::id(), name, format);
+            let path = std::env::temp_dir().join(name);
+            let _ = fs::remove_file(&path);
+            Archive(path)
+        }
+
+        // append the posts up to `last` like `export`, returning the floor it resumed after
+        fn append(&self, format: Format, last: u64) -> u64 {
+            let (mut file, floor) = open(&self.0, format).unwrap();
+            if file.metadata().unwrap().len() == 0 {
+                let thread = Thread {
+                    title: String::from("title"),
+                    content: String::from("<a id=\"floor-99\"></a>"),
+                    ..Thread::default()
+                };
+                write_header(&mut file, &thread, format).unwrap();
+            }
+            let mut csv = csv::Writer::from_writer(file.try_clone().unwrap());
+            for post in (floor + 1..=last).map(post) {
+                match format {
+                    Format::Csv => csv.write_record(csv_record(&post)).unwrap(),
+                    _ => write_post(&mut file, &post, format).unwrap(),
+                }
+            }
+            csv.flush().unwrap();
+            if format == Format::Html {
+                file.write_all(HTML_FOOTER.as_bytes()).unwrap();
+            }
+            floor
+        }
+
+        // drop the last `bytes`, like an interrupted export
+        fn cut(&self, bytes: u64) {
+            let file = OpenOptions::new().write(true).open(&self.0).unwrap();
+            let len = file.metadata().unwrap().len();
+            file.set_len(len - bytes).unwrap();
+        }
+
+        fn len(&self) -> u64 {
+            fs::metadata(&self.0).unwrap().len()
+        }
+
+        // floors of the posts in the archive, in order
+        fn floors(&self, format: Format) -> Vec<u64> {
+            let content = fs::read(&self.0).unwrap();
+            match format {
+                Format::JsonLines => lines(&content)
+                    .map(|line| {
+                        let post: serde_json::Value = serde_json::from_slice(line.text).unwrap();
+                        post["floor"].as_u64().unwrap()
+                    })
+                    .collect(),
+                Format::Csv => csv::Reader::from_reader(&content[..])
+                    .records()
+                    .map(|record| record.unwrap()[0].parse().unwrap())
+                    .collect(),
+                Format::Markdown => lines(&content)
+                    .filter_map(|line| anchor(line.text, MARKDOWN_ANCHOR))
+                    .collect(),
+                Format::Html => lines(&content)
+                    .filter_map(|line| anchor(line.text, HTML_ANCHOR))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Drop for Archive {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn last_floor_of_each_format() {
+        for format in FORMATS {
+            let archive = Archive::new("last", format);
+            assert_eq!(last_floor(&archive.0, format).unwrap(), (0, 0));
+            archive.append(format, 5);
+            let (floor, len) = last_floor(&archive.0, format).unwrap();
+            match format {
+                // the last post is written again
+                Format::Markdown => {
+                    assert_eq!(floor, 4);
+                    assert!(len < archive.len());
+                }
+                // up to the footer
+                Format::Html => {
+                    assert_eq!(floor, 5);
+                    assert_eq!(len, archive.len() - HTML_FOOTER.len() as u64);
+                }
+                _ => assert_eq!((floor, len), (5, archive.len()), "{}", format),
+            }
+        }
+    }
+
+    #[test]
+    fn last_floor_skips_cut_posts() {
+        for format in FORMATS {
+            // in the middle of the content of the last post, after a newline and a quote
+            for cut in [1, 14, 18, 30] {
+                let archive = Archive::new(&format!("cut-{}", cut), format);
+                archive.append(format, 5);
+                let full = archive.len();
+                archive.cut(
+                    cut + if format == Format::Html {
+                        HTML_FOOTER.len() as u64
+                    } else {
+                        0
+                    },
+                );
+                let (floor, len) = last_floor(&archive.0, format).unwrap();
+                assert_eq!(floor, 4, "{} cut by {}", format, cut);
+                assert!(len < full - cut, "{} cut by {}", format, cut);
+            }
+        }
+    }
+
+    #[test]
+    fn posts_cannot_fake_anchors() {
+        assert_eq!(escape_anchors("text"), "text");
+        assert_eq!(
+            escape_anchors("<a id=\"floor-9\">\n<a id=\"floor-9\">"),
+            "\\<a id=\"floor-9\">\n\\<a id=\"floor-9\">"
+        );
+        let archive = Archive::new("fake", Format::Markdown);
+        archive.append(Format::Markdown, 4);
+        assert_eq!(archive.floors(Format::Markdown), [1, 2, 3, 4]);
+        assert_eq!(last_floor(&archive.0, Format::Markdown).unwrap().0, 3);
+    }
+
+    #[test]
+    fn strip_html_footer_only() {
+        let archive = Archive::new("footer", Format::Html);
+        archive.append(Format::Html, 2);
+        let len = archive.len();
+        strip_html_footer(&archive.0).unwrap();
+        assert_eq!(archive.len(), len - HTML_FOOTER.len() as u64);
+        // nothing else is stripped
+        strip_html_footer(&archive.0).unwrap();
+        assert_eq!(archive.len(), len - HTML_FOOTER.len() as u64);
+        assert!(fs::read_to_string(&archive.0)
+            .unwrap()
+            .ends_with("</article>\n"));
+    }
+
+    #[test]
+    fn resume_without_gaps_or_duplicates() {
+        for format in FORMATS {
+            for cut in [0, 1, 14, 30] {
+                let archive = Archive::new(&format!("resume-{}", cut), format);
+                assert_eq!(archive.append(format, 5), 0);
+                archive.cut(cut);
+                let floor = archive.append(format, 8);
+                assert!((3..=5).contains(&floor), "{} cut by {}", format, cut);
+                assert_eq!(
+                    archive.floors(format),
+                    (1..=8).collect::<Vec<_>>(),
+                    "{} cut by {}",
+                    format,
+                    cut
+                );
+                // nothing new
+                archive.append(format, 8);
+                assert_eq!(archive.floors(format), (1..=8).collect::<Vec<_>>());
+            }
+        }
+    }
+}
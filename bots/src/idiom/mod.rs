@@ -0,0 +1,506 @@
+mod pinyin;
+
+use crate::bot::{Handler, Response};
+use crate::play::{self, Idle};
+use crate::schedule::Schedule;
+use crate::settings::{IdiomSettings, Settings};
+use crate::wordle::game::{diff_by, GameOver, Match, Matches};
+use pinyin::{Syllable, Table};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::str::FromStr;
+use std::time::Instant;
+use ykst_client::markdown::{Markdown, Text};
+use ykst_client::model::Post;
+
+/// Characters of an idiom.
+const LEN: usize = 4;
+/// Guesses a game allows.
+const ATTEMPTS: usize = 10;
+
+enum Action {
+    Nop,
+    Start,
+    Guess(String),
+}
+
+#[derive(Debug, Clone)]
+enum ParseActionError {
+    InvalidIdiom(String),
+    EmptyIdiom,
+    UnsupportedAction(String),
+}
+
+impl Display for ParseActionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseActionError::InvalidIdiom(idiom) => write!(
+                f,
+                "❌  {} 为无效成语，请确保成语为{}个汉字组成",
+                Text::new().code(idiom),
+                LEN
+            ),
+            ParseActionError::EmptyIdiom => write!(f, "❌  猜测成语为空，请输入四字成语"),
+            ParseActionError::UnsupportedAction(action) => write!(
+                f,
+                "❌  {} 为不支持的动作，请输入`/start`或`/guess 成语`",
+                Text::new().code(action)
+            ),
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Nop => write!(f, "nop"),
+            Action::Start => write!(f, "/start"),
+            Action::Guess(guess) => write!(f, "/guess {}", guess),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = match play::command(s) {
+            Some(command) => command,
+            None => return Ok(Action::Nop),
+        };
+        match name {
+            "/start" => Ok(Action::Start),
+            "/guess" => match args.first() {
+                Some(guess) if is_idiom(guess) => Ok(Action::Guess(guess.to_string())),
+                Some(guess) => Err(ParseActionError::InvalidIdiom(guess.to_string())),
+                None => Err(ParseActionError::EmptyIdiom),
+            },
+            _ => Err(ParseActionError::UnsupportedAction(name.to_string())),
+        }
+    }
+}
+
+fn is_han(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{20000}'..='\u{2ffff}'
+    )
+}
+
+/// Whether `text` is made of [`LEN`] Chinese characters.
+fn is_idiom(text: &str) -> bool {
+    text.chars().count() == LEN && text.chars().all(is_han)
+}
+
+/// Characters of an idiom with their pinyin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Idiom {
+    text: String,
+    syllables: Vec<Syllable>,
+}
+
+impl Idiom {
+    fn chars(&self) -> Vec<char> {
+        self.text.chars().collect()
+    }
+}
+
+/// The idioms solutions are picked from and guesses are checked against, with their pinyin.
+#[derive(Debug)]
+pub struct Idioms {
+    list: Vec<Idiom>,
+    // index in the list, by text
+    known: HashMap<String, usize>,
+}
+
+impl Idioms {
+    /// Read the files of `IDIOM`.
+    ///
+    /// Every line of `IDIOMS` holds an idiom, optionally followed by the pinyin of its
+    /// characters for those the table reads otherwise; blank lines and lines starting with `#`
+    /// are skipped.
+    pub fn load(settings: &IdiomSettings) -> Result<Self, String> {
+        let table = Table::load(&settings.pinyin)?;
+        let path = &settings.idioms;
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut list = vec![];
+        let mut known = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let text = fields.next().unwrap_or("");
+            if !is_idiom(text) {
+                return Err(format!(
+                    "{}:{}: `{}` is not an idiom of {} characters",
+                    path.display(),
+                    i + 1,
+                    text,
+                    LEN
+                ));
+            }
+            let readings: Vec<&str> = fields.collect();
+            let idiom = if readings.is_empty() {
+                lookup(&table, text)
+                    .map_err(|ch| format!("{}:{}: no pinyin for `{}`", path.display(), i + 1, ch))?
+            } else if readings.len() == LEN {
+                let syllables = readings
+                    .iter()
+                    .map(|reading| reading.parse())
+                    .collect::<Result<_, String>>()
+                    .map_err(|err| format!("{}:{}: {}", path.display(), i + 1, err))?;
+                Idiom {
+                    text: text.to_string(),
+                    syllables,
+                }
+            } else {
+                return Err(format!(
+                    "{}:{}: `{}` needs the pinyin of {} characters",
+                    path.display(),
+                    i + 1,
+                    text,
+                    LEN
+                ));
+            };
+            if !known.contains_key(text) {
+                known.insert(text.to_string(), list.len());
+                list.push(idiom);
+            }
+        }
+        if list.is_empty() {
+            return Err(format!("{}: no idioms", path.display()));
+        }
+        Ok(Idioms { list, known })
+    }
+
+    fn random(&self) -> Idiom {
+        self.list.choose(&mut rand::thread_rng()).unwrap().clone()
+    }
+
+    /// `text` with its pinyin, if it is one of the idioms.
+    fn get(&self, text: &str) -> Result<Idiom, String> {
+        match self.known.get(text) {
+            Some(&i) => Ok(self.list[i].clone()),
+            None => Err(format!(
+                "❌  {} 不在成语词库中，请换一个成语",
+                Text::new().code(text)
+            )),
+        }
+    }
+}
+
+/// `text` with the pinyin of `table`, or the first character missing from it.
+fn lookup(table: &Table, text: &str) -> Result<Idiom, char> {
+    let syllables = text
+        .chars()
+        .map(|ch| table.get(ch).cloned().ok_or(ch))
+        .collect::<Result<_, _>>()?;
+    Ok(Idiom {
+        text: text.to_string(),
+        syllables,
+    })
+}
+
+/// How every character of a guess and every part of its pinyin match the solution.
+#[derive(Debug, Clone)]
+struct Feedback {
+    chars: Matches,
+    initials: Matches,
+    finals: Matches,
+    tones: Matches,
+}
+
+impl Feedback {
+    fn new(guess: &Idiom, solution: &Idiom) -> Self {
+        let initials = |idiom: &Idiom| -> Vec<String> {
+            idiom.syllables.iter().map(|s| s.initial.clone()).collect()
+        };
+        let finals = |idiom: &Idiom| -> Vec<String> {
+            idiom.syllables.iter().map(|s| s.final_.clone()).collect()
+        };
+        let tones = |idiom: &Idiom| -> Vec<u8> { idiom.syllables.iter().map(|s| s.tone).collect() };
+        Feedback {
+            chars: diff_by(&guess.chars(), &solution.chars()),
+            initials: diff_by(&initials(guess), &initials(solution)),
+            finals: diff_by(&finals(guess), &finals(solution)),
+            tones: diff_by(&tones(guess), &tones(solution)),
+        }
+    }
+
+    /// `guess` with every character and every part of its pinyin styled by how it matched.
+    fn render(&self, guess: &Idiom) -> Text {
+        let mut text = Text::new().plain(&self.chars.to_string());
+        for (i, (ch, syllable)) in guess.chars().into_iter().zip(&guess.syllables).enumerate() {
+            text = style(text.plain(" "), &ch.to_string(), self.chars.0[i]).plain("（");
+            if !syllable.initial.is_empty() {
+                text = style(text, &syllable.initial, self.initials.0[i]).plain("·");
+            }
+            text = style(text, &syllable.final_, self.finals.0[i]).plain("·");
+            text = style(text, &syllable.tone.to_string(), self.tones.0[i]).plain("）");
+        }
+        text
+    }
+}
+
+fn style(text: Text, part: &str, m: Match) -> Text {
+    match m {
+        Match::Exact => text.bold_italic(part),
+        Match::Close => text.plain(part),
+        Match::Wrong => text.strikethrough(part),
+    }
+}
+
+struct Game {
+    solution: Idiom,
+    guesses: Vec<(Idiom, Feedback)>,
+    // identity of every guess
+    guessers: Vec<String>,
+    // when the game started or was last guessed in
+    active_at: Instant,
+}
+
+impl Game {
+    fn new(solution: Idiom) -> Self {
+        Game {
+            solution,
+            guesses: vec![],
+            guessers: vec![],
+            active_at: Instant::now(),
+        }
+    }
+
+    fn game_over(&self) -> Option<GameOver> {
+        match self.guesses.last() {
+            Some((guess, _)) if *guess == self.solution => Some(GameOver::Win(self.guesses.len())),
+            _ if self.guesses.len() >= ATTEMPTS => Some(GameOver::Lose),
+            _ => None,
+        }
+    }
+
+    /// Heading with the solution, for the end of the game.
+    fn solution_heading(&self) -> Markdown {
+        let pinyin: Vec<String> = self
+            .solution
+            .syllables
+            .iter()
+            .map(Syllable::to_string)
+            .collect();
+        Markdown::new().heading(
+            2,
+            format!(
+                "{}（{}） {}/{}",
+                self.solution.text,
+                pinyin.join(" "),
+                self.guesses.len(),
+                ATTEMPTS
+            ),
+        )
+    }
+
+    /// Add every guess so far to `reply`.
+    fn history(&self, mut reply: Markdown) -> Markdown {
+        for ((guess, feedback), identity) in self.guesses.iter().zip(&self.guessers) {
+            reply = reply.paragraph(feedback.render(guess).plain("    ").mention(identity));
+        }
+        reply
+    }
+}
+
+/// Chinese idiom Wordle bot of a single thread, known as 汉兜.
+///
+/// Plays a co-op game started with `/start`, in which players guess an idiom of four
+/// characters and learn how its characters and their initials, finals and tones match.
+///
+/// The game is not kept across restarts, there is nothing to save on shutdown.
+pub struct IdiomBot {
+    game: Option<Game>,
+    // none if the files of `IDIOM` can't be read
+    idioms: Option<Idioms>,
+    // games nobody guesses in for this long are ended
+    idle: Option<Idle>,
+}
+
+impl IdiomBot {
+    pub fn new(_thread_id: u64, settings: &Settings) -> Self {
+        let idioms = match Idioms::load(&settings.idiom) {
+            Ok(idioms) => Some(idioms),
+            Err(err) => {
+                error!("idioms: {}", err);
+                None
+            }
+        };
+        IdiomBot {
+            game: None,
+            idioms,
+            idle: Idle::from_minutes(settings.idiom.idle_minutes),
+        }
+    }
+
+    fn start(&mut self) -> Response {
+        if self.game.is_some() {
+            info!("game already started");
+            return Response::Error(String::from("❌  游戏已经开始，请输入`/guess 成语`猜测"));
+        }
+        let idioms = match &self.idioms {
+            Some(idioms) => idioms,
+            None => return Response::Error(String::from("❌  成语词库读取失败，请联系管理员")),
+        };
+        let game = Game::new(idioms.random());
+        info!("game started, answer: {}", game.solution.text);
+        self.game = Some(game);
+        Response::Reply(format!("🚀  成语 Wordle 游戏开始，请输入`/guess 成语`猜测，谜底为{}字成语，一共{}次机会，首先猜对的用户获胜。\n\n每次反馈都包括猜测的历史记录，方格表示每个字和谜底的接近程度：🟩代表该字正确，🟨代表谜底里有该字但位置不对，⬛代表谜底没有该字。\n\n每个字和括号中拼音的声母、韵母、声调也以字体表示：\n\n+ ***斜体加粗***代表该部分正确\n\n+ 正常字体代表谜底里有该部分但位置不对\n\n+ ~~删除~~代表谜底没有该部分", LEN, ATTEMPTS))
+    }
+
+    fn guess(&mut self, post: &Post, post_id: u64, guess: &str) -> Vec<Response> {
+        let (game, idioms) = match (self.game.as_mut(), &self.idioms) {
+            (Some(game), Some(idioms)) => (game, idioms),
+            _ => {
+                info!("game not started");
+                return vec![Response::Error(String::from(
+                    "❌  游戏还未开始，请回复`/start`以开始游戏",
+                ))];
+            }
+        };
+        let guess = match idioms.get(guess) {
+            Ok(guess) => guess,
+            Err(err) => {
+                info!("invalid guess");
+                return vec![Response::Error(err)];
+            }
+        };
+        let feedback = Feedback::new(&guess, &game.solution);
+        game.guesses.push((guess, feedback));
+        game.guessers.push(post.identity_code.clone());
+        game.active_at = Instant::now();
+
+        let mut responses = vec![];
+        let over = game.game_over();
+        let mut reply = match over {
+            Some(_) => game.solution_heading(),
+            None => Markdown::new(),
+        };
+        reply = game.history(reply);
+        match over {
+            Some(GameOver::Win(_)) => {
+                info!("game ends, win");
+                reply = reply.paragraph(format!(" 恭喜{}，小鱼干奉上🎉", post.identity_code));
+                responses.push(Response::Appreciate { post_id, amount: 1 });
+            }
+            Some(GameOver::Lose) => {
+                info!("game ends, lose");
+                reply = reply.paragraph(" 游戏结束，再接再厉💪");
+            }
+            None => {}
+        }
+        if over.is_some() {
+            self.game = None;
+        }
+        responses.push(Response::Reply(reply.to_string()));
+        responses
+    }
+
+    /// End the game if nobody guessed in it for too long.
+    fn end_idle(&mut self) -> Vec<Response> {
+        let idle = match self.idle {
+            Some(idle) => idle,
+            None => return vec![],
+        };
+        match self.game.take_if(|game| idle.is_over(game.active_at)) {
+            Some(game) => {
+                info!("game abandoned, answer: {}", game.solution.text);
+                let reply = game.history(game.solution_heading()).paragraph(format!(
+                    " ⌛  {}分钟无人猜测，游戏自动结束，可以开始新游戏了",
+                    idle.minutes()
+                ));
+                vec![Response::Reply(reply.to_string())]
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Handler for IdiomBot {
+    fn handle(&mut self, post: &Post) -> Vec<Response> {
+        let post_id = match &post.model {
+            Some(model) => model.id,
+            None => {
+                warn!("post.model is none");
+                return vec![];
+            }
+        };
+        let action = match post.content.parse::<Action>() {
+            Ok(action) => action,
+            Err(err) => {
+                info!("failed to parse action");
+                return vec![Response::Error(err.to_string())];
+            }
+        };
+        info!("floor: {} action: {}", post.floor, action);
+        match action {
+            Action::Start => vec![self.start()],
+            Action::Guess(guess) => self.guess(post, post_id, &guess),
+            Action::Nop => vec![],
+        }
+    }
+
+    fn stop(&mut self) -> Vec<Response> {
+        match self.game.take() {
+            Some(game) => {
+                info!("game stopped by admin");
+                vec![Response::Reply(format!(
+                    "🛑  游戏已被管理员终止，谜底为 {}",
+                    Text::new().bold_italic(&game.solution.text)
+                ))]
+            }
+            None => vec![],
+        }
+    }
+
+    fn status(&self) -> String {
+        match &self.game {
+            Some(game) => format!("🎮  游戏进行中，已猜{}/{}次", game.guesses.len(), ATTEMPTS),
+            None if self.idioms.is_none() => String::from("⚠️  成语词库读取失败"),
+            None => String::from("💤  当前没有进行中的游戏"),
+        }
+    }
+
+    fn schedules(&self) -> Vec<(String, Schedule)> {
+        self.idle.iter().map(Idle::schedule).collect()
+    }
+
+    fn on_schedule(&mut self, name: &str) -> Vec<Response> {
+        match name {
+            play::IDLE => self.end_idle(),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_must_be_idioms() {
+        let idiom = |text: &str, readings: [&str; LEN]| Idiom {
+            text: text.to_string(),
+            syllables: readings.iter().map(|r| r.parse().unwrap()).collect(),
+        };
+        let idioms = Idioms {
+            list: vec![idiom("一马当先", ["yi1", "ma3", "dang1", "xian1"])],
+            known: HashMap::from([(String::from("一马当先"), 0)]),
+        };
+        assert_eq!(idioms.get("一马当先").unwrap(), idioms.list[0]);
+        // four characters with pinyin, but not an idiom
+        assert!(idioms.get("一马先当").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// longest first, so that `zh` is not taken for `z`
+const INITIALS: [&str; 23] = [
+    "zh", "ch", "sh", "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "j", "q", "x", "r",
+    "z", "c", "s", "y", "w",
+];
+
+/// Pinyin of one character, split into initial, final and tone.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Syllable {
+    /// Empty for syllables without one, e.g. `an`.
+    pub initial: String,
+    pub final_: String,
+    /// 1 to 4, 0 for the neutral tone.
+    pub tone: u8,
+}
+
+impl FromStr for Syllable {
+    type Err = String;
+
+    /// Parse a syllable with tone marks, e.g. `zhōng`, or with a tone number, e.g. `zhong1`;
+    /// `v` stands for `ü`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut letters = String::new();
+        let mut tone = 0;
+        for ch in s.trim().to_lowercase().chars() {
+            match ch {
+                '1'..='5' if tone == 0 => tone = ch as u8 - b'0',
+                'v' => letters.push('ü'),
+                _ => match unmark(ch) {
+                    Some((base, mark)) => {
+                        letters.push(base);
+                        tone = mark;
+                    }
+                    None if ch.is_ascii_lowercase() || ch == 'ü' => letters.push(ch),
+                    None => return Err(format!("invalid pinyin `{}`", s)),
+                },
+            }
+        }
+        if letters.is_empty() {
+            return Err(format!("invalid pinyin `{}`", s));
+        }
+        let initial = INITIALS
+            .iter()
+            .find(|initial| letters.starts_with(*initial) && letters.len() > initial.len())
+            .copied()
+            .unwrap_or("");
+        Ok(Syllable {
+            initial: initial.to_string(),
+            final_: letters[initial.len()..].to_string(),
+            // the fifth tone is the neutral one
+            tone: tone % 5,
+        })
+    }
+}
+
+impl Display for Syllable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.initial, self.final_, self.tone)
+    }
+}
+
+/// Base letter and tone of a vowel with a tone mark.
+fn unmark(ch: char) -> Option<(char, u8)> {
+    const MARKED: [(char, [char; 4]); 8] = [
+        ('a', ['ā', 'á', 'ǎ', 'à']),
+        ('e', ['ē', 'é', 'ě', 'è']),
+        ('i', ['ī', 'í', 'ǐ', 'ì']),
+        ('o', ['ō', 'ó', 'ǒ', 'ò']),
+        ('u', ['ū', 'ú', 'ǔ', 'ù']),
+        ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+        ('n', ['\0', 'ń', 'ň', 'ǹ']),
+        ('m', ['\0', 'ḿ', '\0', '\0']),
+    ];
+    MARKED.iter().find_map(|(base, marks)| {
+        marks
+            .iter()
+            .position(|&mark| mark == ch)
+            .map(|i| (*base, i as u8 + 1))
+    })
+}
+
+/// Pinyin of every character, read from a table like those of
+/// [pinyin-data](https://github.com/mozillazg/pinyin-data).
+#[derive(Debug, Default)]
+pub struct Table(HashMap<char, Syllable>);
+
+impl Table {
+    /// Read the table at `path`.
+    ///
+    /// Every line holds a character, as is or as `U+XXXX:`, and its readings separated by commas
+    /// or spaces, of which the first is used; text after `#` is ignored.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut table = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || format!("{}:{}: invalid line", path.display(), i + 1);
+            let (ch, readings) = match line.strip_prefix("U+") {
+                Some(rest) => {
+                    let (code, readings) = rest.split_once(':').ok_or_else(invalid)?;
+                    let ch = u32::from_str_radix(code.trim(), 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(invalid)?;
+                    (ch, readings)
+                }
+                None => {
+                    let ch = line.chars().next().ok_or_else(invalid)?;
+                    (ch, &line[ch.len_utf8()..])
+                }
+            };
+            let reading = readings
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .find(|reading| !reading.is_empty())
+                .ok_or_else(invalid)?;
+            let syllable = reading
+                .parse()
+                .map_err(|err| format!("{}:{}: {}", path.display(), i + 1, err))?;
+            table.entry(ch).or_insert(syllable);
+        }
+        if table.is_empty() {
+            return Err(format!("{}: no characters", path.display()));
+        }
+        Ok(Table(table))
+    }
+
+    pub fn get(&self, ch: char) -> Option<&Syllable> {
+        self.0.get(&ch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllable(initial: &str, final_: &str, tone: u8) -> Syllable {
+        Syllable {
+            initial: initial.to_string(),
+            final_: final_.to_string(),
+            tone,
+        }
+    }
+
+    #[test]
+    fn parse_marks_and_numbers() {
+        assert_eq!("zhōng".parse(), Ok(syllable("zh", "ong", 1)));
+        assert_eq!("zhong1".parse(), Ok(syllable("zh", "ong", 1)));
+        assert_eq!("lǜ".parse(), Ok(syllable("l", "ü", 4)));
+        assert_eq!("lv4".parse(), Ok(syllable("l", "ü", 4)));
+        assert_eq!("ān".parse(), Ok(syllable("", "an", 1)));
+        assert_eq!("de".parse(), Ok(syllable("d", "e", 0)));
+        assert_eq!("ma5".parse(), Ok(syllable("m", "a", 0)));
+        // `n` alone is a final, not an initial
+        assert_eq!("ň".parse(), Ok(syllable("", "n", 3)));
+    }
+
+    #[test]
+    fn parse_refuses_other_text() {
+        assert!("".parse::<Syllable>().is_err());
+        assert!("3".parse::<Syllable>().is_err());
+        assert!("中".parse::<Syllable>().is_err());
+    }
+
+    #[test]
+    fn load_both_line_formats() {
+        let path = std::env::temp_dir().join(format!("pinyin-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "# comment\nU+4E2D: zhōng,zhòng  # 中\n国 guó\n\n中 zhong4\n",
+        )
+        .unwrap();
+        let table = Table::load(&path);
+        fs::write(&path, "国\n").unwrap();
+        let invalid = Table::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let table = table.unwrap();
+        assert_eq!(table.get('中'), Some(&syllable("zh", "ong", 1)));
+        assert_eq!(table.get('国'), Some(&syllable("g", "uo", 2)));
+        assert_eq!(table.get('字'), None);
+        assert!(invalid.unwrap_err().ends_with(":1: invalid line"));
+    }
+}
@@ -0,0 +1,53 @@
+use serde_json::{json, Value};
+use ykst_client::model::{Model, Post, Thread, User};
+use ykst_client::Category;
+
+// id and creation time in seconds
+fn model_json(model: &Option<Model>) -> (Value, Value) {
+    match model {
+        Some(model) => (
+            json!(model.id),
+            json!(model.created_at.as_ref().map(|time| time.seconds)),
+        ),
+        None => (Value::Null, Value::Null),
+    }
+}
+
+/// JSON of a user.
+pub fn user_json(user: &User) -> Value {
+    let (id, created_at) = model_json(&user.model);
+    json!({ "id": id, "created_at": created_at, "nickname": user.nickname })
+}
+
+/// JSON of a thread, without its posts.
+pub fn thread_json(thread: &Thread) -> Value {
+    let (id, created_at) = model_json(&thread.model);
+    json!({
+        "id": id,
+        "created_at": created_at,
+        "category": Category::from_id(thread.category_id).map(Category::name),
+        "title": thread.title,
+        "content": thread.content,
+        "identity_code": thread.identity_code,
+        "tags": thread.tags.iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>(),
+        "reply_count": thread.reply_count,
+        "like_count": thread.like_count,
+        "dislike_count": thread.dislike_count,
+    })
+}
+
+/// JSON of a post.
+pub fn post_json(post: &Post) -> Value {
+    let (id, created_at) = model_json(&post.model);
+    json!({
+        "id": id,
+        "created_at": created_at,
+        "thread_id": post.thread_id,
+        "reply_to_post_id": post.reply_to_post_id,
+        "floor": post.floor,
+        "content": post.content,
+        "identity_code": post.identity_code,
+        "like_count": post.like_count,
+        "dislike_count": post.dislike_count,
+    })
+}
@@ -0,0 +1,34 @@
+#[macro_use]
+extern crate log;
+
+pub mod admin;
+pub mod bot;
+pub mod export;
+pub mod idiom;
+pub mod json;
+pub mod play;
+pub mod poller;
+pub mod reload;
+pub mod runner;
+pub mod schedule;
+pub mod settings;
+pub mod shutdown;
+pub mod throttle;
+pub mod wordle;
+
+use bot::Handler;
+use idiom::IdiomBot;
+use settings::Settings;
+use wordle::WordleBot;
+
+/// Bot types which can be configured in `BOTS`.
+pub const KINDS: [&str; 2] = ["wordle", "idiom"];
+
+/// Create a handler for the thread `thread_id` of a bot of type `kind`, as configured in `BOTS`.
+pub fn new_handler(kind: &str, thread_id: u64, settings: &Settings) -> Option<Box<dyn Handler>> {
+    match kind {
+        "wordle" => Some(Box::new(WordleBot::new(thread_id, settings))),
+        "idiom" => Some(Box::new(IdiomBot::new(thread_id, settings))),
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+//! Parts shared by the game bots: reading commands from posts and ending idle games.
+
+use crate::schedule::Schedule;
+use std::time::{Duration, Instant};
+
+/// Name of the schedule checking for idle games.
+pub const IDLE: &str = "idle";
+
+/// Command `content` starts with, with the words after it, or none if `content` doesn't start
+/// with `/`.
+pub fn command(content: &str) -> Option<(&str, Vec<&str>)> {
+    let mut tokens = content.split_whitespace();
+    let name = tokens.next().filter(|name| name.starts_with('/'))?;
+    Some((name, tokens.collect()))
+}
+
+/// How long a game nobody plays in lasts, from `IDLE_MINUTES`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idle(Duration);
+
+impl Idle {
+    /// None for 0 minutes, games then never end idle.
+    pub fn from_minutes(minutes: u64) -> Option<Self> {
+        (minutes > 0).then(|| Idle(Duration::from_secs(minutes * 60)))
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.0.as_secs() / 60
+    }
+
+    /// Whether a game last played at `active_at` has been idle for too long.
+    pub fn is_over(&self, active_at: Instant) -> bool {
+        active_at.elapsed() >= self.0
+    }
+
+    /// Schedule named [`IDLE`] to look for idle games every minute.
+    pub fn schedule(&self) -> (String, Schedule) {
+        (String::from(IDLE), Schedule::Every(Duration::from_secs(60)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_start_with_a_slash() {
+        assert_eq!(
+            command("  /guess  crane\nthanks"),
+            Some(("/guess", vec!["crane", "thanks"]))
+        );
+        assert_eq!(command("/start"), Some(("/start", vec![])));
+        assert_eq!(command("guess /start"), None);
+        assert_eq!(command(""), None);
+    }
+
+    #[test]
+    fn idle_of_zero_minutes_is_none() {
+        assert_eq!(Idle::from_minutes(0), None);
+        let idle = Idle::from_minutes(30).unwrap();
+        assert_eq!(idle.minutes(), 30);
+        assert!(!idle.is_over(Instant::now()));
+        assert!(idle.is_over(Instant::now() - Duration::from_secs(30 * 60)));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use std::error::Error;
+use ykst_client::model::Post;
+use ykst_client::AuthClient;
+
+/// Fetches new posts of a thread, skipping those written by the bot itself or by other bots.
+pub struct Poller {
+    thread_id: u64,
+    floor: u64,
+    ignored: HashSet<String>,
+}
+
+impl Poller {
+    /// Create a poller starting after the current last floor of `thread_id`.
+    ///
+    /// Posts by `client.identity` are always ignored, as are posts by any of `bot_identities`.
+    pub async fn new(
+        client: &mut AuthClient,
+        thread_id: u64,
+        bot_identities: &[String],
+    ) -> Result<Self, Box<dyn Error>> {
+        let thread = client.get_thread(thread_id).await?;
+        let mut poller = Poller {
+            thread_id,
+            floor: thread.reply_count,
+            ignored: HashSet::new(),
+        };
+        poller.set_ignored(&client.identity, bot_identities);
+        Ok(poller)
+    }
+
+    /// Ignore posts by `identity`, the bot itself, and by `bot_identities` instead of the
+    /// identities given so far.
+    pub fn set_ignored(&mut self, identity: &str, bot_identities: &[String]) {
+        self.ignored = bot_identities.iter().cloned().collect();
+        self.ignored.insert(identity.to_string());
+    }
+
+    pub fn thread_id(&self) -> u64 {
+        self.thread_id
+    }
+
+    pub fn floor(&self) -> u64 {
+        self.floor
+    }
+
+    /// Fetch the posts after the last seen floor.
+    pub async fn poll(&mut self, client: &mut AuthClient) -> Result<Vec<Post>, Box<dyn Error>> {
+        let replies = client
+            .get_thread_replies(self.thread_id, self.floor, 19)
+            .await?;
+        let mut posts = vec![];
+        for post in replies.posts {
+            self.floor = post.floor; // update post floor
+            if self.ignored.contains(&post.identity_code) {
+                debug!(
+                    "skip post by bot {} at floor {}",
+                    post.identity_code, post.floor
+                );
+                continue;
+            }
+            posts.push(post);
+        }
+        Ok(posts)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::settings::{Need, Settings};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::time::sleep;
+
+// keys read when the handler of a thread is created
+const HANDLER_KEYS: [&str; 3] = ["DATA_DIR", "WORDLE", "IDIOM"];
+
+/// Notices changes of the config file by checking its modification time.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        ConfigWatcher { path, modified }
+    }
+
+    /// Resolves once the file has been modified since the last change noticed.
+    pub async fn changed(&mut self) {
+        loop {
+            sleep(Duration::from_secs(2)).await;
+            let modified = modified(&self.path);
+            if modified != self.modified {
+                self.modified = modified;
+                return;
+            }
+        }
+    }
+
+    /// Load the modified settings and check them for `needs`.
+    ///
+    /// Returns `None`, so that `current` stays in use, if the new settings are invalid or equal
+    /// to `current`. Keys which can only be applied by restarting are reported but kept.
+    pub fn reload(&self, current: &Settings, needs: &[Need]) -> Option<Settings> {
+        let mut settings = match Settings::load(&self.path) {
+            Ok(settings) => settings,
+            Err(err) => {
+                error!("reject config reload, keeping the current config: {}", err);
+                return None;
+            }
+        };
+        let problems = settings.check(needs);
+        if !problems.is_empty() {
+            error!(
+                "reject config reload, keeping the current config: {}",
+                problems.join("; ")
+            );
+            return None;
+        }
+        if settings.api_url != current.api_url {
+            warn!("API_URL changed, restart to apply");
+            settings.api_url = current.api_url.clone();
+        }
+        let changes = current.changes(&settings);
+        if changes.is_empty() {
+            info!("config reloaded, nothing changed");
+            return None;
+        }
+        info!("config reloaded, changed: {}", changes.join(", "));
+        let handler_changes: Vec<_> = changes
+            .iter()
+            .filter(|key| HANDLER_KEYS.contains(key))
+            .copied()
+            .collect();
+        if !handler_changes.is_empty() {
+            info!(
+                "{} apply to threads started or reset with `/admin reset` from now on",
+                handler_changes.join(", ")
+            );
+        }
+        Some(settings)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
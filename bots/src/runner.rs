@@ -0,0 +1,535 @@
+use crate::admin::AdminCommand;
+use crate::bot::{Handler, Response};
+use crate::poller::Poller;
+use crate::schedule::{self, Timer};
+use crate::throttle::{Limits, Throttle, Verdict, MAX_MUTE};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use ykst_client::markdown::{self, Markdown, Text, MAX_POST_LEN};
+use ykst_client::model::Post;
+use ykst_client::AuthClient;
+
+// delay before restarting a crashed thread task, doubled after every crash
+const MIN_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+// a task running at least this long before crashing starts over with `MIN_BACKOFF`
+const STABLE_RUN: Duration = Duration::from_secs(600);
+// how long `Runner::shutdown` waits for all threads to send their last replies
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Watches a set of threads with one client, running a separate [`Handler`] for each of them.
+///
+/// A thread whose handler panics is restarted with a fresh handler after an increasing delay.
+pub struct Runner<H> {
+    client: AuthClient,
+    new_handler: Arc<dyn Fn(u64) -> H + Send + Sync>,
+    options: watch::Sender<Options>,
+    threads: HashMap<u64, Watch>,
+}
+
+/// Settings of a [`Runner`] which can be changed while it is running.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Identity to post with.
+    pub identity: String,
+    /// Identities of other bots, whose posts are ignored just like our own.
+    pub bot_identities: Vec<String>,
+    /// Posted to every thread when the runner shuts down.
+    pub offline_notice: Option<String>,
+    /// Identities allowed to use `/admin` commands, never throttled.
+    pub owners: Vec<String>,
+    /// Limits on the commands of every other identity.
+    pub limits: Limits,
+    /// Post the [`Response::Error`]s of one polling batch as a single reply.
+    pub batch_errors: bool,
+}
+
+struct Watch {
+    command: watch::Sender<Command>,
+    task: JoinHandle<()>,
+}
+
+/// What the runner wants a thread task to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    /// The thread was removed from the watched threads.
+    Stop,
+    /// The whole bot goes offline.
+    Shutdown,
+}
+
+impl<H: Handler> Runner<H> {
+    /// `new_handler` is called with the thread id whenever a thread starts being watched.
+    pub fn new<F>(client: AuthClient, options: Options, new_handler: F) -> Self
+    where
+        F: Fn(u64) -> H + Send + Sync + 'static,
+    {
+        Runner {
+            client,
+            new_handler: Arc::new(new_handler),
+            options: watch::channel(options).0,
+            threads: HashMap::new(),
+        }
+    }
+
+    /// Apply `options` to all threads, including those already watched.
+    pub fn set_options(&mut self, options: Options) {
+        self.options.send_replace(options);
+    }
+
+    /// Start watching the threads in `thread_ids` which are not watched yet, and stop watching
+    /// those no longer listed, dropping their state.
+    pub fn set_threads(&mut self, thread_ids: &[u64]) {
+        self.threads.retain(|thread_id, watch| {
+            if thread_ids.contains(thread_id) {
+                true
+            } else {
+                // the task finishes the posts it is handling, then exits on its own
+                info!("stop watching thread {}", thread_id);
+                let _ = watch.command.send(Command::Stop);
+                false
+            }
+        });
+        for &thread_id in thread_ids {
+            if self.threads.contains_key(&thread_id) {
+                continue;
+            }
+            info!("start watching thread {}", thread_id);
+            let (command, commands) = watch::channel(Command::Run);
+            let task = tokio::spawn(supervise(
+                self.client.clone(),
+                thread_id,
+                self.new_handler.clone(),
+                self.options.subscribe(),
+                commands,
+            ));
+            self.threads.insert(thread_id, Watch { command, task });
+        }
+    }
+
+    /// Stop watching all threads, waiting until the replies in flight and the replies of
+    /// [`Handler::on_shutdown`] have been sent, for 30 seconds at most.
+    pub async fn shutdown(mut self) {
+        for watch in self.threads.values() {
+            let _ = watch.command.send(Command::Shutdown);
+        }
+        // the tasks stop concurrently, so one deadline bounds the whole shutdown
+        let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+        for (thread_id, watch) in self.threads.drain() {
+            match tokio::time::timeout_at(deadline, watch.task).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => error!("thread {} task: {}", thread_id, err),
+                Err(_) => warn!(
+                    "thread {} did not stop in {:?}",
+                    thread_id, SHUTDOWN_TIMEOUT
+                ),
+            }
+        }
+    }
+}
+
+/// Resolves once the runner asks the task to stop.
+async fn stopped(commands: &mut watch::Receiver<Command>) {
+    while *commands.borrow() == Command::Run {
+        if commands.changed().await.is_err() {
+            // runner dropped
+            return;
+        }
+    }
+}
+
+async fn supervise<H: Handler>(
+    client: AuthClient,
+    thread_id: u64,
+    new_handler: Arc<dyn Fn(u64) -> H + Send + Sync>,
+    options: watch::Receiver<Options>,
+    mut commands: watch::Receiver<Command>,
+) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let started = Instant::now();
+        let task = tokio::spawn(watch_thread(
+            client.clone(),
+            ThreadState::new(thread_id, new_handler.clone()),
+            options.clone(),
+            commands.clone(),
+        ));
+        match task.await {
+            Ok(()) => return,
+            Err(err) => error!("thread {} crashed: {}", thread_id, err),
+        }
+        if started.elapsed() >= STABLE_RUN {
+            backoff = MIN_BACKOFF;
+        }
+        warn!("restart thread {} in {:?}", thread_id, backoff);
+        tokio::select! {
+            _ = sleep(backoff) => {}
+            _ = stopped(&mut commands) => return,
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// A watched thread: its handler and what the runner keeps track of around it.
+struct ThreadState<H> {
+    thread_id: u64,
+    handler: H,
+    new_handler: Arc<dyn Fn(u64) -> H + Send + Sync>,
+    // only admin commands are handled while paused
+    paused: bool,
+    throttle: Throttle,
+    timers: Vec<Timer>,
+}
+
+impl<H: Handler> ThreadState<H> {
+    fn new(thread_id: u64, new_handler: Arc<dyn Fn(u64) -> H + Send + Sync>) -> Self {
+        let handler = new_handler(thread_id);
+        ThreadState {
+            thread_id,
+            timers: timers(&handler),
+            handler,
+            new_handler,
+            paused: false,
+            throttle: Throttle::default(),
+        }
+    }
+
+    /// Run the handler's schedules which are due; schedules due while paused are skipped.
+    fn run_timers(&mut self) -> Vec<Response> {
+        let now = schedule::now();
+        let mut responses = vec![];
+        for timer in &mut self.timers {
+            if !timer.fire(&now) || self.paused {
+                continue;
+            }
+            info!("thread {} schedule: {}", self.thread_id, timer.name);
+            responses.extend(self.handler.on_schedule(&timer.name));
+        }
+        responses
+    }
+
+    /// Handle the posts fetched by one poll, throttling the commands of identities other than
+    /// the owners.
+    ///
+    /// Throttled commands are dropped; the identities warned about them are gathered in a single
+    /// reply after the other responses, and so are the errors with `batch_errors`.
+    fn handle_batch(&mut self, posts: &[Post], options: &Options) -> Vec<Response> {
+        let now = Instant::now();
+        let mut responses = vec![];
+        let mut errors = vec![];
+        let mut handle = |thread: &mut Self, post: &Post| {
+            for response in thread.handle(post, options, now) {
+                match response {
+                    Response::Error(error) if options.batch_errors => {
+                        errors.push((post.identity_code.clone(), error))
+                    }
+                    response => responses.push(response),
+                }
+            }
+        };
+        let mut too_fast = vec![];
+        let mut cooldowns = vec![];
+        let mut muted = vec![];
+        for post in posts {
+            let identity = &post.identity_code;
+            let command = match command_name(&post.content) {
+                Some(command) if !options.owners.contains(identity) => command,
+                _ => {
+                    handle(self, post);
+                    continue;
+                }
+            };
+            match self.throttle.check(identity, command, &options.limits, now) {
+                Verdict::Allow => handle(self, post),
+                Verdict::TooFast { warn } => {
+                    info!("floor: {} {} too fast", post.floor, identity);
+                    if warn {
+                        too_fast.push(Text::new().mention(identity).to_string());
+                    }
+                }
+                Verdict::Cooldown { remaining, warn } => {
+                    info!(
+                        "floor: {} {} /{} cooling down",
+                        post.floor, identity, command
+                    );
+                    if warn {
+                        cooldowns.push(format!(
+                            "{}还需等待{}秒",
+                            Text::new()
+                                .mention(identity)
+                                .plain(" ")
+                                .code(&format!("/{}", command)),
+                            remaining.as_secs().max(1)
+                        ));
+                    }
+                }
+                Verdict::Muted(duration) => {
+                    warn!("floor: {} mute {} for {:?}", post.floor, identity, duration);
+                    muted.push(Text::new().mention(identity).to_string());
+                }
+                Verdict::StillMuted => info!("floor: {} {} muted", post.floor, identity),
+            }
+        }
+
+        if errors.len() == 1 {
+            // a lone error is posted as it is
+            responses.push(Response::Error(errors.remove(0).1));
+        } else if !errors.is_empty() {
+            let reply =
+                Markdown::new()
+                    .paragraph(format!("⚠️  {}条无效命令：", errors.len()))
+                    .list(errors.iter().map(|(identity, error)| {
+                        Text::new().mention(identity).raw("：").raw(error)
+                    }));
+            responses.push(Response::Error(reply.to_string()));
+        }
+
+        let mut warnings = vec![];
+        if !too_fast.is_empty() {
+            warnings.push(format!(
+                "🐢  {} 操作太快，每{}秒最多{}条命令，请稍后再试",
+                too_fast.join(" "),
+                options.limits.window_secs,
+                options.limits.commands
+            ));
+        }
+        if !cooldowns.is_empty() {
+            warnings.push(format!("⏳  命令冷却中：{}", cooldowns.join("，")));
+        }
+        if !muted.is_empty() {
+            warnings.push(format!(
+                "🔇  {} 多次操作过快，{}分钟内的命令将被忽略",
+                muted.join(" "),
+                minutes(options.limits.mute_secs)
+            ));
+        }
+        if !warnings.is_empty() {
+            responses.push(Response::Reply(warnings.join("\n\n")));
+        }
+        responses
+    }
+
+    fn handle(&mut self, post: &Post, options: &Options, now: Instant) -> Vec<Response> {
+        let command = match AdminCommand::parse(&post.content) {
+            Some(command) => command,
+            None if self.paused => return vec![],
+            None => return self.handler.handle(post),
+        };
+        if !options.owners.contains(&post.identity_code) {
+            info!("refuse admin command of {}", post.identity_code);
+            return vec![Response::Error(String::from(
+                "🙅  抱歉，只有bot管理员才能使用`/admin`命令",
+            ))];
+        }
+        let command = match command {
+            Ok(command) => command,
+            Err(command) => {
+                return vec![Response::Error(format!(
+                    "❌  {} 为不支持的管理命令，请输入`/admin stop`、`/admin reset`、`/admin pause`、`/admin resume`、`/admin status`、`/admin mute 身份 [分钟]`或`/admin unmute 身份`",
+                    Text::new().code(&command)
+                ))]
+            }
+        };
+        info!("floor: {} admin: {}", post.floor, command);
+        let reply = match command {
+            AdminCommand::Stop => {
+                let responses = self.handler.stop();
+                if !responses.is_empty() {
+                    return responses;
+                }
+                String::from("🛑  当前没有需要停止的内容")
+            }
+            AdminCommand::Reset => {
+                self.handler = (self.new_handler)(self.thread_id);
+                self.timers = timers(&self.handler);
+                String::from("🔄  bot已重置")
+            }
+            AdminCommand::Pause => {
+                self.paused = true;
+                String::from("⏸️  bot已暂停，回复`/admin resume`以恢复")
+            }
+            AdminCommand::Resume => {
+                self.paused = false;
+                String::from("▶️  bot已恢复")
+            }
+            AdminCommand::Status => {
+                let mut reply = String::from(if self.paused {
+                    "⏸️  bot已暂停"
+                } else {
+                    "▶️  bot运行中"
+                });
+                let status = self.handler.status();
+                if !status.is_empty() {
+                    reply.push_str("\n\n");
+                    reply.push_str(&status);
+                }
+                let muted: Vec<_> = self
+                    .throttle
+                    .muted(now)
+                    .into_iter()
+                    .map(|(identity, left)| {
+                        format!(
+                            "{}（{}分钟）",
+                            Text::new().mention(identity),
+                            minutes(left.as_secs())
+                        )
+                    })
+                    .collect();
+                if !muted.is_empty() {
+                    reply.push_str(&format!("\n\n🔇  禁言中：{}", muted.join(" ")));
+                }
+                reply
+            }
+            AdminCommand::Mute {
+                identity,
+                minutes: length,
+            } => {
+                let length = length
+                    .unwrap_or_else(|| minutes(options.limits.mute_secs))
+                    .min(MAX_MUTE.as_secs() / 60);
+                self.throttle
+                    .mute(&identity, Duration::from_secs(length * 60), now);
+                format!(
+                    "🔇  已禁言{} {}分钟",
+                    Text::new().mention(&identity),
+                    length
+                )
+            }
+            AdminCommand::Unmute(identity) => {
+                if self.throttle.unmute(&identity) {
+                    format!("🔊  已解除{}的禁言", Text::new().mention(&identity))
+                } else {
+                    format!("❌  {} 未被禁言", Text::new().mention(&identity))
+                }
+            }
+        };
+        vec![Response::Reply(reply)]
+    }
+}
+
+async fn watch_thread<H: Handler>(
+    mut client: AuthClient,
+    mut thread: ThreadState<H>,
+    mut options: watch::Receiver<Options>,
+    mut commands: watch::Receiver<Command>,
+) {
+    let thread_id = thread.thread_id;
+    poll_thread(&mut client, &mut thread, &mut options, &mut commands).await;
+    for response in thread.handler.on_shutdown() {
+        respond(&mut client, thread_id, response).await;
+    }
+    let shutdown = *commands.borrow() == Command::Shutdown;
+    let offline_notice = options.borrow().offline_notice.clone();
+    if let (true, Some(notice)) = (shutdown, offline_notice) {
+        respond(&mut client, thread_id, Response::Reply(notice)).await;
+    }
+    info!("thread {} stopped", thread_id);
+}
+
+/// Handle the new posts of the thread until the runner asks to stop.
+async fn poll_thread<H: Handler>(
+    client: &mut AuthClient,
+    thread: &mut ThreadState<H>,
+    options: &mut watch::Receiver<Options>,
+    commands: &mut watch::Receiver<Command>,
+) {
+    let thread_id = thread.thread_id;
+    let mut current = options.borrow_and_update().clone();
+    client.identity = current.identity.clone();
+    let mut poller = loop {
+        match Poller::new(client, thread_id, &current.bot_identities).await {
+            Ok(poller) => break poller,
+            Err(err) => error!("get_thread {}: {}", thread_id, err),
+        }
+        tokio::select! {
+            _ = sleep(Duration::from_secs(10)) => {}
+            _ = stopped(commands) => return,
+        }
+    };
+    info!("thread {} floor: {}", thread_id, poller.floor());
+
+    loop {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(2)) => {}
+            _ = stopped(commands) => return,
+        }
+        if options.has_changed().unwrap_or(false) {
+            current = options.borrow_and_update().clone();
+            client.identity = current.identity.clone();
+            poller.set_ignored(&current.identity, &current.bot_identities);
+        }
+        for response in thread.run_timers() {
+            respond(client, thread_id, response).await;
+        }
+        let posts = match poller.poll(client).await {
+            Ok(posts) => Some(posts),
+            Err(err) => {
+                error!("get_thread_replies {}: {}", thread_id, err);
+                // back off; the error is not `Send` so it must be dropped before sleeping
+                None
+            }
+        };
+        let posts = match posts {
+            Some(posts) => posts,
+            None => {
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(8)) => {}
+                    _ = stopped(commands) => return,
+                }
+                continue;
+            }
+        };
+        // posts already fetched are handled even when asked to stop meanwhile
+        for response in thread.handle_batch(&posts, &current) {
+            respond(client, thread_id, response).await;
+        }
+    }
+}
+
+fn timers(handler: &impl Handler) -> Vec<Timer> {
+    handler
+        .schedules()
+        .into_iter()
+        .map(|(name, schedule)| Timer::new(name, schedule))
+        .collect()
+}
+
+/// Name of the command a post starts with, without the leading `/`.
+fn command_name(content: &str) -> Option<&str> {
+    content.split_whitespace().next()?.strip_prefix('/')
+}
+
+/// `secs` in whole minutes, rounded up.
+fn minutes(secs: u64) -> u64 {
+    secs.div_ceil(60)
+}
+
+async fn respond(client: &mut AuthClient, thread_id: u64, response: Response) {
+    match response {
+        Response::Reply(content) | Response::Error(content) => {
+            // long replies are posted in parts
+            for part in markdown::split(&content, MAX_POST_LEN) {
+                if let Err(err) = client.reply_to_thread(thread_id, part).await {
+                    error!("reply_to_thread {}: {}", thread_id, err);
+                }
+            }
+        }
+        Response::ReplyTo { post_id, content } => {
+            for part in markdown::split(&content, MAX_POST_LEN) {
+                if let Err(err) = client.reply_to_post(thread_id, Some(post_id), part).await {
+                    error!("reply_to_post {}: {}", post_id, err);
+                }
+            }
+        }
+        Response::Appreciate { post_id, amount } => {
+            if let Err(err) = client.appreciate_post(post_id, amount).await {
+                error!("appreciate_post {}: {}", post_id, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::DateTime;
+use chrono_tz::Tz;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Timezone schedules are evaluated in.
+pub const TIMEZONE: Tz = chrono_tz::Asia::Shanghai;
+
+/// Current time in [`TIMEZONE`].
+pub fn now() -> DateTime<Tz> {
+    chrono::Utc::now().with_timezone(&TIMEZONE)
+}
+
+/// When a scheduled task runs.
+///
+/// Parsed from a cron expression with seconds, e.g. `0 0 8 * * *` for 8:00 every day, or from
+/// `@every <n><s|m|h|d>`, e.g. `@every 30m`.
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Cron(Box<cron::Schedule>),
+    Every(Duration),
+}
+
+impl Schedule {
+    /// First time the task runs after `time`, if ever.
+    pub fn after(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match self {
+            Schedule::Cron(schedule) => schedule.after(time).next(),
+            Schedule::Every(interval) => Some(*time + chrono::Duration::from_std(*interval).ok()?),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ParseScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let interval = match s.strip_prefix("@every") {
+            Some(interval) => interval.trim(),
+            None => {
+                return cron::Schedule::from_str(s)
+                    .map(|schedule| Schedule::Cron(Box::new(schedule)))
+                    .map_err(|err| ParseScheduleError(format!("`{}`: {}", s, err)))
+            }
+        };
+        let unit = match interval.chars().last() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => {
+                return Err(ParseScheduleError(format!(
+                    "`{}`: interval must end with s, m, h or d",
+                    s
+                )))
+            }
+        };
+        match interval[..interval.len() - 1].parse::<u64>() {
+            Ok(n) if n > 0 => Ok(Schedule::Every(Duration::from_secs(n * unit))),
+            _ => Err(ParseScheduleError(format!("`{}`: invalid interval", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseScheduleError(String);
+
+impl Display for ParseScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid schedule {}", self.0)
+    }
+}
+
+impl Error for ParseScheduleError {}
+
+/// A named schedule and the next time it is due.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub name: String,
+    schedule: Schedule,
+    next: Option<DateTime<Tz>>,
+}
+
+impl Timer {
+    pub fn new(name: String, schedule: Schedule) -> Self {
+        let next = schedule.after(&now());
+        Timer {
+            name,
+            schedule,
+            next,
+        }
+    }
+
+    /// Whether the timer is due at `now`, moving it to its next time if so.
+    ///
+    /// Runs missed while the bot was busy are not made up for.
+    pub fn fire(&mut self, now: &DateTime<Tz>) -> bool {
+        match self.next {
+            Some(next) if next <= *now => {
+                self.next = self.schedule.after(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use crate::idiom::Idioms;
+use crate::schedule::Schedule;
+use crate::throttle::Limits;
+use crate::wordle::words::WordList;
+use config::{Config, ConfigError, File, FileFormat};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the environment variables overriding config.yaml, e.g. `YKST_TREEHOLE_TOKEN`.
+pub const ENV_PREFIX: &str = "YKST_";
+// keys whose environment variable holds a comma separated list
+const LIST_KEYS: [&str; 3] = ["THREAD_IDS", "BOT_IDENTITIES", "OWNER_IDENTITIES"];
+
+/// Settings read from config.yaml, shared by all binaries.
+///
+/// Missing keys are left empty; use [`Settings::check`] to find out whether the settings are
+/// complete for what a binary does.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub api_url: String,
+    pub treehole_token: String,
+    pub identity_code: String,
+    pub thread_ids: Vec<u64>,
+    pub bot_identities: Vec<String>,
+    pub owner_identities: Vec<String>,
+    pub offline_notice: Option<String>,
+    pub throttle: Limits,
+    pub batch_errors: bool,
+    pub bots: Vec<BotSettings>,
+    /// directory bots keep their data in, the working directory if empty
+    pub data_dir: PathBuf,
+    pub wordle: WordleSettings,
+    pub idiom: IdiomSettings,
+    pub checkin: CheckinSettings,
+    pub auth_api_url: String,
+    pub auth_redirect_url: String,
+    /// keys with a value of the wrong type, and why
+    invalid: Vec<(&'static str, String)>,
+}
+
+/// One entry of `BOTS`, a bot run by ykst-botd.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct BotSettings {
+    /// bot type, one of [`crate::KINDS`]
+    #[serde(rename = "TYPE")]
+    pub kind: String,
+    /// identity the bot posts with, defaults to `IDENTITY_CODE`
+    pub identity_code: Option<String>,
+    pub thread_ids: Vec<u64>,
+    /// defaults to `BATCH_ERRORS`
+    pub batch_errors: Option<bool>,
+}
+
+/// `WORDLE`, options of the wordle bots.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", default)]
+pub struct WordleSettings {
+    /// a game nobody guesses in for this long is ended, 0 to never end it
+    pub idle_minutes: u64,
+    /// hints a game allows with `/hint`, 0 to turn hints off
+    pub hints: usize,
+    /// fish recorded against the player in their statistics for every hint
+    pub hint_penalty: u32,
+    /// list of `/start` without a list and of the daily puzzle
+    pub default_list: String,
+    pub lists: Vec<WordListSettings>,
+}
+
+impl Default for WordleSettings {
+    fn default() -> Self {
+        WordleSettings {
+            idle_minutes: 30,
+            hints: 1,
+            hint_penalty: 0,
+            default_list: String::from(crate::wordle::words::BUILTIN),
+            lists: vec![],
+        }
+    }
+}
+
+/// One entry of `WORDLE.LISTS`, a word list read from files.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct WordListSettings {
+    /// name picked with `/start <name>`
+    pub name: String,
+    /// file of the words solutions are picked from, one per line
+    pub solutions: PathBuf,
+    /// file of further words accepted as guesses, the built-in list if not given
+    pub allowed: Option<PathBuf>,
+}
+
+/// `IDIOM`, options of the idiom bots.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", default)]
+pub struct IdiomSettings {
+    /// a game nobody guesses in for this long is ended, 0 to never end it
+    pub idle_minutes: u64,
+    /// file of the idioms solutions are picked from, one per line
+    pub idioms: PathBuf,
+    /// file of the pinyin of every character
+    pub pinyin: PathBuf,
+}
+
+impl Default for IdiomSettings {
+    fn default() -> Self {
+        IdiomSettings {
+            idle_minutes: 30,
+            idioms: PathBuf::new(),
+            pinyin: PathBuf::new(),
+        }
+    }
+}
+
+/// `CHECKIN`, the accounts checked in every day by checkin-bot.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", default)]
+pub struct CheckinSettings {
+    /// when to check in, see [`Schedule`]
+    pub schedule: String,
+    /// up to this many seconds are added to every run at random
+    pub jitter_secs: u64,
+    /// attempts per account before giving up until the next run
+    pub attempts: u32,
+    /// CSV file the fish balance of every check-in is appended to
+    pub history_file: PathBuf,
+    pub accounts: Vec<CheckinAccount>,
+}
+
+impl Default for CheckinSettings {
+    fn default() -> Self {
+        CheckinSettings {
+            schedule: String::from("0 0 8 * * *"),
+            jitter_secs: 1800,
+            attempts: 3,
+            history_file: PathBuf::from("checkin.csv"),
+            accounts: vec![],
+        }
+    }
+}
+
+/// One entry of `CHECKIN.ACCOUNTS`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct CheckinAccount {
+    /// name of the account in the history and the summary
+    pub name: String,
+    /// defaults to `TREEHOLE_TOKEN`
+    pub treehole_token: Option<String>,
+}
+
+/// Parts of the settings a binary relies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Need {
+    /// `API_URL`, `TREEHOLE_TOKEN` and `IDENTITY_CODE` to connect to the treehole.
+    Client,
+    /// `THREAD_IDS` to watch.
+    Threads,
+    /// `BOTS` for ykst-botd, and `IDIOM` if it runs idiom bots or its files are set.
+    Bots,
+    /// `AUTH_API_URL` and `AUTH_REDIRECT_URL` for get-token.
+    Auth,
+    /// `API_URL` and `CHECKIN` for checkin-bot.
+    Checkin,
+    /// `WORDLE` for the wordle bots, reading every word list.
+    Wordle,
+}
+
+impl Settings {
+    /// Read the settings from `path` and the `YKST_*` environment variables.
+    ///
+    /// Values of the wrong type are left empty and reported by [`Settings::check`].
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let mut builder = Config::builder().add_source(File::new(
+            path.to_str()
+                .ok_or_else(|| ConfigError::Message(format!("invalid path {}", path.display())))?,
+            FileFormat::Yaml,
+        ));
+        for (key, value) in env::vars() {
+            let key = match key.strip_prefix(ENV_PREFIX) {
+                Some(key) => key.to_string(),
+                None => continue,
+            };
+            builder = if LIST_KEYS.contains(&key.as_str()) {
+                let values: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+                    .collect();
+                builder.set_override(key, values)?
+            } else {
+                builder.set_override(key, value)?
+            };
+        }
+        let config = builder.build()?;
+
+        let mut invalid = vec![];
+        let mut thread_ids: Vec<u64> = get(&config, "THREAD_IDS", &mut invalid);
+        // the single thread of configs older than `THREAD_IDS`
+        if let Some(thread_id) = get::<Option<u64>>(&config, "THREAD_ID", &mut invalid) {
+            warn!("THREAD_ID is deprecated, use THREAD_IDS: [{}]", thread_id);
+            if !thread_ids.contains(&thread_id) {
+                thread_ids.push(thread_id);
+            }
+        }
+        Ok(Settings {
+            api_url: get(&config, "API_URL", &mut invalid),
+            treehole_token: get(&config, "TREEHOLE_TOKEN", &mut invalid),
+            identity_code: get(&config, "IDENTITY_CODE", &mut invalid),
+            thread_ids,
+            bot_identities: get(&config, "BOT_IDENTITIES", &mut invalid),
+            owner_identities: get(&config, "OWNER_IDENTITIES", &mut invalid),
+            offline_notice: get(&config, "OFFLINE_NOTICE", &mut invalid),
+            throttle: get(&config, "THROTTLE", &mut invalid),
+            batch_errors: get(&config, "BATCH_ERRORS", &mut invalid),
+            bots: get(&config, "BOTS", &mut invalid),
+            data_dir: get(&config, "DATA_DIR", &mut invalid),
+            wordle: get(&config, "WORDLE", &mut invalid),
+            idiom: get(&config, "IDIOM", &mut invalid),
+            checkin: get(&config, "CHECKIN", &mut invalid),
+            auth_api_url: get(&config, "AUTH_API_URL", &mut invalid),
+            auth_redirect_url: get(&config, "AUTH_REDIRECT_URL", &mut invalid),
+            invalid,
+        })
+    }
+
+    /// Keys whose value differs in `other`.
+    pub fn changes(&self, other: &Settings) -> Vec<&'static str> {
+        let mut changes = vec![];
+        let mut compare = |key, changed| {
+            if changed {
+                changes.push(key);
+            }
+        };
+        compare("API_URL", self.api_url != other.api_url);
+        compare(
+            "TREEHOLE_TOKEN",
+            self.treehole_token != other.treehole_token,
+        );
+        compare("IDENTITY_CODE", self.identity_code != other.identity_code);
+        compare("THREAD_IDS", self.thread_ids != other.thread_ids);
+        compare(
+            "BOT_IDENTITIES",
+            self.bot_identities != other.bot_identities,
+        );
+        compare(
+            "OWNER_IDENTITIES",
+            self.owner_identities != other.owner_identities,
+        );
+        compare(
+            "OFFLINE_NOTICE",
+            self.offline_notice != other.offline_notice,
+        );
+        compare("THROTTLE", self.throttle != other.throttle);
+        compare("BATCH_ERRORS", self.batch_errors != other.batch_errors);
+        compare("BOTS", self.bots != other.bots);
+        compare("DATA_DIR", self.data_dir != other.data_dir);
+        compare("WORDLE", self.wordle != other.wordle);
+        compare("IDIOM", self.idiom != other.idiom);
+        compare("CHECKIN", self.checkin != other.checkin);
+        compare("AUTH_API_URL", self.auth_api_url != other.auth_api_url);
+        compare(
+            "AUTH_REDIRECT_URL",
+            self.auth_redirect_url != other.auth_redirect_url,
+        );
+        changes
+    }
+
+    /// Every problem preventing a binary relying on `needs` from running.
+    pub fn check(&self, needs: &[Need]) -> Vec<String> {
+        let mut problems = Problems {
+            invalid: &self.invalid,
+            problems: vec![],
+        };
+        for need in needs {
+            match need {
+                Need::Client => {
+                    problems.check_url("API_URL", &self.api_url);
+                    problems.check_token("TREEHOLE_TOKEN", &self.treehole_token);
+                    problems.check_present("IDENTITY_CODE", &self.identity_code);
+                    problems.check_valid("BOT_IDENTITIES");
+                    problems.check_valid("OWNER_IDENTITIES");
+                    problems.check_valid("OFFLINE_NOTICE");
+                    if problems.check_valid("THROTTLE") {
+                        if self.throttle.commands == 0 {
+                            problems.push("THROTTLE.COMMANDS: must allow at least one command");
+                        }
+                        if self.throttle.mute_after == 0 {
+                            problems.push("THROTTLE.MUTE_AFTER: must be at least 1");
+                        }
+                    }
+                    problems.check_valid("BATCH_ERRORS");
+                    problems.check_valid("DATA_DIR");
+                }
+                Need::Threads => {
+                    if problems.check_valid("THREAD_IDS")
+                        && problems.check_valid("THREAD_ID")
+                        && self.thread_ids.is_empty()
+                    {
+                        problems.push("THREAD_IDS: no thread to watch");
+                    }
+                }
+                Need::Bots => {
+                    if problems.check_valid("BOTS") && self.bots.is_empty() {
+                        problems.push("BOTS: no bot to run");
+                    }
+                    for (i, bot) in self.bots.iter().enumerate() {
+                        if !crate::KINDS.contains(&bot.kind.as_str()) {
+                            problems
+                                .push(format!("BOTS[{}].TYPE: unknown bot type `{}`", i, bot.kind));
+                        }
+                        if bot.thread_ids.is_empty() {
+                            problems.push(format!("BOTS[{}].THREAD_IDS: no thread to watch", i));
+                        }
+                    }
+                    // idiom bots have no built-in idioms to fall back on, and files set for bots
+                    // added later are checked as well
+                    let idioms_set = !self.idiom.idioms.as_os_str().is_empty()
+                        || !self.idiom.pinyin.as_os_str().is_empty();
+                    if (idioms_set || self.bots.iter().any(|bot| bot.kind == "idiom"))
+                        && problems.check_valid("IDIOM")
+                    {
+                        if self.idiom.idioms.as_os_str().is_empty() {
+                            problems.push("IDIOM.IDIOMS: missing");
+                        } else if self.idiom.pinyin.as_os_str().is_empty() {
+                            problems.push("IDIOM.PINYIN: missing");
+                        } else if let Err(err) = Idioms::load(&self.idiom) {
+                            problems.push(format!("IDIOM: {}", err));
+                        }
+                    }
+                }
+                Need::Auth => {
+                    problems.check_url("AUTH_API_URL", &self.auth_api_url);
+                    problems.check_url("AUTH_REDIRECT_URL", &self.auth_redirect_url);
+                }
+                Need::Wordle => {
+                    if !problems.check_valid("WORDLE") {
+                        continue;
+                    }
+                    let mut names = vec![crate::wordle::words::BUILTIN];
+                    for (i, list) in self.wordle.lists.iter().enumerate() {
+                        if names.contains(&list.name.as_str()) {
+                            problems.push(format!(
+                                "WORDLE.LISTS[{}].NAME: `{}` is taken",
+                                i, list.name
+                            ));
+                        } else if let Err(err) = WordList::load(list) {
+                            problems.push(format!("WORDLE.LISTS[{}]: {}", i, err));
+                        }
+                        names.push(&list.name);
+                    }
+                    if !names.contains(&self.wordle.default_list.as_str()) {
+                        problems.push(format!(
+                            "WORDLE.DEFAULT_LIST: unknown list `{}`",
+                            self.wordle.default_list
+                        ));
+                    }
+                }
+                Need::Checkin => {
+                    problems.check_url("API_URL", &self.api_url);
+                    if !problems.check_valid("CHECKIN") {
+                        continue;
+                    }
+                    let checkin = &self.checkin;
+                    if let Err(err) = checkin.schedule.parse::<Schedule>() {
+                        problems.push(format!("CHECKIN.SCHEDULE: {}", err));
+                    }
+                    if checkin.attempts == 0 {
+                        problems.push("CHECKIN.ATTEMPTS: must be at least 1");
+                    }
+                    if checkin.accounts.is_empty() {
+                        problems.push("CHECKIN.ACCOUNTS: no account to check in");
+                    }
+                    for (i, account) in checkin.accounts.iter().enumerate() {
+                        match &account.treehole_token {
+                            Some(token) => problems.check_token(
+                                &format!("CHECKIN.ACCOUNTS[{}].TREEHOLE_TOKEN", i),
+                                token,
+                            ),
+                            None if self.treehole_token.is_empty() => problems.push(format!(
+                                "CHECKIN.ACCOUNTS[{}].TREEHOLE_TOKEN: missing, and no TREEHOLE_TOKEN to default to",
+                                i
+                            )),
+                            None => {}
+                        }
+                    }
+                    let defaulted = checkin
+                        .accounts
+                        .iter()
+                        .any(|account| account.treehole_token.is_none());
+                    if defaulted && !self.treehole_token.is_empty() {
+                        problems.check_token("TREEHOLE_TOKEN", &self.treehole_token);
+                    }
+                }
+            }
+        }
+        problems.problems
+    }
+}
+
+/// Read `key`, recording it as invalid and falling back to the default if its value has the
+/// wrong type.
+fn get<T: DeserializeOwned + Default>(
+    config: &Config,
+    key: &'static str,
+    invalid: &mut Vec<(&'static str, String)>,
+) -> T {
+    match config.get(key) {
+        Ok(value) => value,
+        Err(ConfigError::NotFound(_)) => T::default(),
+        Err(err) => {
+            invalid.push((key, err.to_string()));
+            T::default()
+        }
+    }
+}
+
+struct Problems<'a> {
+    invalid: &'a [(&'static str, String)],
+    problems: Vec<String>,
+}
+
+impl Problems<'_> {
+    fn push(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    /// Record the problem if `key` has a value of the wrong type, returning whether it is valid.
+    fn check_valid(&mut self, key: &str) -> bool {
+        match self.invalid.iter().find(|(invalid, _)| *invalid == key) {
+            Some((_, err)) => {
+                self.problems.push(format!("{}: {}", key, err));
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn check_present(&mut self, key: &str, value: &str) {
+        if self.check_valid(key) && value.is_empty() {
+            self.push(format!("{}: missing", key));
+        }
+    }
+
+    /// Record the problem if the token `value` is missing or can't be sent in a request.
+    fn check_token(&mut self, key: &str, value: &str) {
+        if !self.check_valid(key) {
+            return;
+        }
+        if value.is_empty() {
+            self.push(format!("{}: missing", key));
+        } else if !ykst_client::is_valid_token(value) {
+            self.push(format!(
+                "{}: not a valid token, only visible ASCII characters are allowed",
+                key
+            ));
+        }
+    }
+
+    fn check_url(&mut self, key: &str, value: &str) {
+        if !self.check_valid(key) {
+            return;
+        }
+        if value.is_empty() {
+            self.push(format!("{}: missing", key));
+        } else if !(value.starts_with("http://") || value.starts_with("https://")) {
+            self.push(format!("{}: `{}` is not an http(s) URL", key, value));
+        }
+    }
+}
+
+/// Command line options selecting and checking the config file.
+#[derive(Debug, clap::Args)]
+pub struct ConfigArgs {
+    /// Path of the config file
+    #[arg(long = "config", default_value = "config.yaml")]
+    pub path: PathBuf,
+    /// Report every problem of the config file and exit
+    #[arg(long)]
+    pub check_config: bool,
+}
+
+impl ConfigArgs {
+    /// Load the settings for a binary relying on `needs`.
+    ///
+    /// Prints every problem and exits when the settings are invalid. With `--check-config`, exits
+    /// after checking in any case.
+    pub fn load(&self, needs: &[Need]) -> Settings {
+        let settings = match Settings::load(&self.path) {
+            Ok(settings) => settings,
+            Err(err) => {
+                eprintln!("{}: {}", self.path.display(), err);
+                std::process::exit(1);
+            }
+        };
+        let problems = settings.check(needs);
+        if !problems.is_empty() {
+            eprintln!("{}: {} problem(s)", self.path.display(), problems.len());
+            for problem in problems {
+                eprintln!("  {}", problem);
+            }
+            std::process::exit(1);
+        }
+        if self.check_config {
+            println!("{}: OK", self.path.display());
+            std::process::exit(0);
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn load(name: &str, yaml: &str) -> Settings {
+        let path = env::temp_dir().join(format!("ykst-bots-{}-{}.yaml", name, std::process::id()));
+        fs::write(&path, yaml).unwrap();
+        let settings = Settings::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        settings
+    }
+
+    #[test]
+    fn thread_id_is_still_accepted() {
+        let settings = load("thread-id", "THREAD_ID: 42\n");
+        assert_eq!(settings.thread_ids, vec![42]);
+        assert!(settings.check(&[Need::Threads]).is_empty());
+    }
+
+    #[test]
+    fn thread_id_adds_to_thread_ids() {
+        let settings = load("both", "THREAD_ID: 42\nTHREAD_IDS: [1, 42]\n");
+        assert_eq!(settings.thread_ids, vec![1, 42]);
+    }
+
+    #[test]
+    fn sample_lists_can_be_read() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config.sample.yaml");
+        let settings = Settings::load(&path).unwrap();
+        assert!(settings.check(&[Need::Wordle]).is_empty());
+    }
+
+    #[test]
+    fn unreadable_lists_are_reported() {
+        let settings = load(
+            "lists",
+            "WORDLE:\n  LISTS:\n    - NAME: cet4\n      SOLUTIONS: missing/cet4.txt\n\
+             IDIOM:\n  IDIOMS: missing/idioms.txt\n  PINYIN: missing/pinyin.txt\n\
+             BOTS:\n  - TYPE: wordle\n    THREAD_IDS: [42]\n",
+        );
+        let problems = settings.check(&[Need::Bots, Need::Wordle]);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("IDIOM: "));
+        assert!(problems[1].starts_with("WORDLE.LISTS[0]: "));
+    }
+}
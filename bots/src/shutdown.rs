@@ -0,0 +1,23 @@
+use std::io;
+
+/// Resolves once the process is asked to stop, by SIGINT (`Ctrl-C`) or SIGTERM.
+#[cfg(unix)]
+pub async fn signal() -> io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = interrupt.recv() => info!("received SIGINT"),
+        _ = terminate.recv() => info!("received SIGTERM"),
+    }
+    Ok(())
+}
+
+/// Resolves once the process is asked to stop by `Ctrl-C`.
+#[cfg(not(unix))]
+pub async fn signal() -> io::Result<()> {
+    tokio::signal::ctrl_c().await?;
+    info!("received Ctrl-C");
+    Ok(())
+}
@@ -0,0 +1,356 @@
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Longest mute, a year; longer ones are cut to it.
+pub const MAX_MUTE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Limits on how fast one identity may send commands to a bot, `THROTTLE` in config.yaml.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", default)]
+pub struct Limits {
+    /// Commands allowed per identity within `window_secs`.
+    pub commands: usize,
+    pub window_secs: u64,
+    /// An identity going over the limit this many times within a window gets muted.
+    pub mute_after: usize,
+    pub mute_secs: u64,
+    /// Seconds an identity has to wait before using a command again, by command name without
+    /// the leading `/`, e.g. `start: 60`.
+    pub cooldowns: HashMap<String, u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            commands: 5,
+            window_secs: 60,
+            mute_after: 3,
+            mute_secs: 600,
+            cooldowns: HashMap::new(),
+        }
+    }
+}
+
+/// What to do with a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// Over the limit; `warn` is set for the first time in a window only.
+    TooFast {
+        warn: bool,
+    },
+    /// The command was used too recently by the same identity.
+    Cooldown {
+        remaining: Duration,
+        warn: bool,
+    },
+    /// Muted just now for going over the limit too often.
+    Muted(Duration),
+    /// Muted before, the command is dropped silently.
+    StillMuted,
+}
+
+/// Tracks the commands of every identity in a thread.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    // times of the recent commands of each identity
+    commands: HashMap<String, VecDeque<Instant>>,
+    // times each identity recently went over the limit
+    strikes: HashMap<String, VecDeque<Instant>>,
+    // last use of a command with a cooldown, by identity and command
+    last_used: HashMap<(String, String), Instant>,
+    // last warning sent to each identity
+    warned: HashMap<String, Instant>,
+    // end of the mute of each muted identity
+    muted: HashMap<String, Instant>,
+    // last time the entries of identities gone quiet were dropped
+    pruned_at: Option<Instant>,
+}
+
+impl Throttle {
+    /// Check `command` (without the leading `/`) sent by `identity` at `now`, and record it.
+    pub fn check(
+        &mut self,
+        identity: &str,
+        command: &str,
+        limits: &Limits,
+        now: Instant,
+    ) -> Verdict {
+        let window = Duration::from_secs(limits.window_secs);
+        if self
+            .pruned_at
+            .is_none_or(|at| now.saturating_duration_since(at) >= window)
+        {
+            self.prune(limits, now);
+        }
+        if let Some(&until) = self.muted.get(identity) {
+            if now < until {
+                return Verdict::StillMuted;
+            }
+            self.muted.remove(identity);
+        }
+
+        let commands = self.commands.entry(identity.to_string()).or_default();
+        expire(commands, now, window);
+        if commands.len() >= limits.commands {
+            let strikes = self.strikes.entry(identity.to_string()).or_default();
+            expire(strikes, now, window);
+            strikes.push_back(now);
+            if strikes.len() >= limits.mute_after {
+                let duration = Duration::from_secs(limits.mute_secs).min(MAX_MUTE);
+                self.mute(identity, duration, now);
+                return Verdict::Muted(duration);
+            }
+            return Verdict::TooFast {
+                warn: self.warn(identity, now, window),
+            };
+        }
+        // commands refused for their cooldown count too, or spamming one would never be muted
+        commands.push_back(now);
+
+        if let Some(&cooldown) = limits.cooldowns.get(command) {
+            let key = (identity.to_string(), command.to_string());
+            let cooldown = Duration::from_secs(cooldown);
+            if let Some(&last) = self.last_used.get(&key) {
+                let elapsed = now.saturating_duration_since(last);
+                if elapsed < cooldown {
+                    return Verdict::Cooldown {
+                        remaining: cooldown - elapsed,
+                        warn: self.warn(identity, now, cooldown - elapsed),
+                    };
+                }
+            }
+            self.last_used.insert(key, now);
+        }
+        Verdict::Allow
+    }
+
+    /// Drop the commands of `identity` for `duration`, at most [`MAX_MUTE`].
+    pub fn mute(&mut self, identity: &str, duration: Duration, now: Instant) {
+        self.muted
+            .insert(identity.to_string(), now + duration.min(MAX_MUTE));
+        self.strikes.remove(identity);
+    }
+
+    /// Returns whether `identity` was muted.
+    pub fn unmute(&mut self, identity: &str) -> bool {
+        self.muted.remove(identity).is_some()
+    }
+
+    /// Identities muted at `now`, with the time left.
+    pub fn muted(&self, now: Instant) -> Vec<(&str, Duration)> {
+        let mut muted: Vec<_> = self
+            .muted
+            .iter()
+            .filter(|(_, &until)| until > now)
+            .map(|(identity, &until)| (identity.as_str(), until - now))
+            .collect();
+        muted.sort();
+        muted
+    }
+
+    /// Drop what can no longer change a verdict at `now`, so that every identity ever seen
+    /// doesn't stay around.
+    fn prune(&mut self, limits: &Limits, now: Instant) {
+        let window = Duration::from_secs(limits.window_secs);
+        for times in [&mut self.commands, &mut self.strikes] {
+            times.retain(|_, times| {
+                expire(times, now, window);
+                !times.is_empty()
+            });
+        }
+        self.last_used.retain(|(_, command), &mut last| {
+            limits.cooldowns.get(command).is_some_and(|&cooldown| {
+                now.saturating_duration_since(last) < Duration::from_secs(cooldown)
+            })
+        });
+        // warnings are held back for a window or a cooldown at most
+        let longest = limits
+            .cooldowns
+            .values()
+            .map(|&cooldown| Duration::from_secs(cooldown))
+            .fold(window, Duration::max);
+        self.warned
+            .retain(|_, &mut last| now.saturating_duration_since(last) < longest);
+        self.muted.retain(|_, &mut until| now < until);
+        self.pruned_at = Some(now);
+    }
+
+    /// Whether to warn `identity` now, at most once per `interval`.
+    fn warn(&mut self, identity: &str, now: Instant, interval: Duration) -> bool {
+        match self.warned.get(identity) {
+            Some(&last) if now.saturating_duration_since(last) < interval => false,
+            _ => {
+                self.warned.insert(identity.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+fn expire(times: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while times
+        .front()
+        .is_some_and(|&time| now.saturating_duration_since(time) >= window)
+    {
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn too_fast_then_muted() {
+        let limits = Limits::default();
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        for i in 0..5 {
+            assert_eq!(
+                throttle.check("a", "guess", &limits, start + i * SEC),
+                Verdict::Allow
+            );
+        }
+        let at = start + 5 * SEC;
+        assert_eq!(
+            throttle.check("a", "guess", &limits, at),
+            Verdict::TooFast { warn: true }
+        );
+        assert_eq!(
+            throttle.check("a", "guess", &limits, at),
+            Verdict::TooFast { warn: false }
+        );
+        // others are not affected
+        assert_eq!(throttle.check("b", "guess", &limits, at), Verdict::Allow);
+        assert_eq!(
+            throttle.check("a", "guess", &limits, at),
+            Verdict::Muted(600 * SEC)
+        );
+        assert_eq!(
+            throttle.check("a", "guess", &limits, at + 599 * SEC),
+            Verdict::StillMuted
+        );
+        assert_eq!(
+            throttle.check("a", "guess", &limits, at + 600 * SEC),
+            Verdict::Allow
+        );
+    }
+
+    #[test]
+    fn commands_expire_after_the_window() {
+        let limits = Limits::default();
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        for _ in 0..5 {
+            throttle.check("a", "guess", &limits, start);
+        }
+        assert_ne!(throttle.check("a", "guess", &limits, start), Verdict::Allow);
+        assert_eq!(
+            throttle.check("a", "guess", &limits, start + 60 * SEC),
+            Verdict::Allow
+        );
+    }
+
+    #[test]
+    fn cooldowns_apply_per_command() {
+        let limits = Limits {
+            cooldowns: HashMap::from([("start".to_string(), 60)]),
+            ..Limits::default()
+        };
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        assert_eq!(throttle.check("a", "start", &limits, start), Verdict::Allow);
+        assert_eq!(
+            throttle.check("a", "start", &limits, start + 20 * SEC),
+            Verdict::Cooldown {
+                remaining: 40 * SEC,
+                warn: true
+            }
+        );
+        assert_eq!(
+            throttle.check("a", "guess", &limits, start + 20 * SEC),
+            Verdict::Allow
+        );
+        assert_eq!(
+            throttle.check("b", "start", &limits, start + 20 * SEC),
+            Verdict::Allow
+        );
+        assert_eq!(
+            throttle.check("a", "start", &limits, start + 60 * SEC),
+            Verdict::Allow
+        );
+    }
+
+    #[test]
+    fn spamming_a_cooldown_is_muted() {
+        let limits = Limits {
+            cooldowns: HashMap::from([("start".to_string(), 60)]),
+            ..Limits::default()
+        };
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        let verdicts: Vec<_> = (0..8)
+            .map(|i| throttle.check("a", "start", &limits, start + i * SEC))
+            .collect();
+        assert_eq!(verdicts[0], Verdict::Allow);
+        assert!(matches!(verdicts[4], Verdict::Cooldown { .. }));
+        assert_eq!(verdicts[5], Verdict::TooFast { warn: false });
+        assert_eq!(verdicts[7], Verdict::Muted(600 * SEC));
+    }
+
+    #[test]
+    fn quiet_identities_are_dropped() {
+        let limits = Limits {
+            cooldowns: HashMap::from([("start".to_string(), 120)]),
+            ..Limits::default()
+        };
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        for i in 0..100 {
+            let identity = format!("player {}", i);
+            for _ in 0..6 {
+                throttle.check(&identity, "start", &limits, start);
+            }
+        }
+        throttle.mute("muted", 30 * SEC, start);
+        assert_eq!(throttle.commands.len(), 100);
+        assert_eq!(throttle.last_used.len(), 100);
+
+        // commands and strikes expire after the window, cooldowns and warnings after the cooldown
+        throttle.check("other", "guess", &limits, start + 60 * SEC);
+        assert_eq!(throttle.commands.len(), 1);
+        assert!(throttle.strikes.is_empty());
+        assert_eq!(throttle.last_used.len(), 100);
+        assert!(throttle.muted.is_empty());
+        throttle.check("other", "guess", &limits, start + 120 * SEC);
+        assert!(throttle.last_used.is_empty());
+        assert_eq!(throttle.warned.len(), 0);
+        assert_eq!(throttle.commands.len(), 1);
+    }
+
+    #[test]
+    fn mutes_are_bounded() {
+        let limits = Limits {
+            commands: 1,
+            mute_after: 1,
+            mute_secs: u64::MAX,
+            ..Limits::default()
+        };
+        let mut throttle = Throttle::default();
+        let now = Instant::now();
+        throttle.check("a", "guess", &limits, now);
+        assert_eq!(
+            throttle.check("a", "guess", &limits, now),
+            Verdict::Muted(MAX_MUTE)
+        );
+        throttle.mute("b", Duration::MAX, now);
+        assert_eq!(throttle.muted(now), vec![("a", MAX_MUTE), ("b", MAX_MUTE)]);
+        assert!(throttle.unmute("a"));
+        assert!(!throttle.unmute("a"));
+        assert_eq!(throttle.muted(now + MAX_MUTE), vec![]);
+    }
+}
@@ -0,0 +1,223 @@
+use super::game::Game;
+use super::stats::{Outcome, Stats};
+use super::words::WordList;
+use crate::schedule;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ykst_client::markdown::{Markdown, Text};
+
+/// Number of the puzzle of `day`, counted like the original Wordle from 2021-06-19.
+pub fn number(day: NaiveDate) -> usize {
+    let first = NaiveDate::from_ymd_opt(2021, 6, 19).unwrap();
+    (day - first).num_days().max(0) as usize
+}
+
+/// Number of today's puzzle in [`schedule::TIMEZONE`].
+pub fn today() -> usize {
+    number(schedule::now().date_naive())
+}
+
+// what is saved between restarts: the guesses of every player, replayed on load
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Saved {
+    number: usize,
+    guesses: BTreeMap<String, Vec<String>>,
+}
+
+/// The daily puzzle of a thread: one answer for everyone, a separate game for every player.
+pub struct Daily {
+    path: PathBuf,
+    number: usize,
+    words: Arc<WordList>,
+    players: BTreeMap<String, Game>,
+}
+
+impl Daily {
+    /// Read the games saved at `path`, starting today's puzzle of `words` if there are none.
+    ///
+    /// Games of an earlier day are kept until [`Daily::roll_over`] announces them.
+    pub fn load(path: PathBuf, words: Arc<WordList>) -> Self {
+        let saved = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                error!("{}: {}, starting over", path.display(), err);
+                Saved::default()
+            }),
+            Err(_) => Saved::default(),
+        };
+        let mut daily = Daily {
+            path,
+            number: saved.number,
+            words,
+            players: BTreeMap::new(),
+        };
+        if saved.guesses.is_empty() {
+            daily.number = today();
+        }
+        for (identity, guesses) in saved.guesses {
+            let mut game = daily.game();
+            for guess in guesses {
+                if game.guess(&guess).is_err() {
+                    warn!("saved daily guess {} of {} is invalid", guess, identity);
+                }
+            }
+            daily.players.insert(identity, game);
+        }
+        daily
+    }
+
+    fn game(&self) -> Game {
+        Game::from_day(self.number, self.words.clone())
+    }
+
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Letters of the solution.
+    pub fn len(&self) -> usize {
+        self.words.default_len()
+    }
+
+    /// Today's game of `identity`, if they started it.
+    pub fn game_of(&self, identity: &str) -> Option<&Game> {
+        self.players.get(identity)
+    }
+
+    /// Players who started today's puzzle, and how many of them are done.
+    pub fn progress(&self) -> (usize, usize) {
+        let done = self
+            .players
+            .values()
+            .filter(|game| game.game_over().is_some())
+            .count();
+        (self.players.len(), done)
+    }
+
+    /// Guess `word` for `identity`, returning the reply and whether the guess solved the puzzle.
+    ///
+    /// Finished games are recorded in `stats` right away.
+    pub fn guess(
+        &mut self,
+        identity: &str,
+        word: &str,
+        stats: &mut Stats,
+    ) -> Result<(String, bool), String> {
+        if !self.players.contains_key(identity) {
+            let game = self.game();
+            self.players.insert(identity.to_string(), game);
+        }
+        let game = self.players.get_mut(identity).unwrap();
+        if game.game_over().is_some() {
+            return Err(format!(
+                "❌  {} 今天已经完成了每日 Wordle #{}，结果将在今日结束时公布",
+                Text::new().mention(identity),
+                self.number
+            ));
+        }
+        if let Err(err) = game.guess(word) {
+            let reply = super::guess_error(&err, word, &self.words);
+            if game.guesses().next().is_none() {
+                // don't count players who never made a valid guess
+                self.players.remove(identity);
+            }
+            return Err(reply);
+        }
+        let over = game.game_over();
+        let won = over.as_ref().is_some_and(|end| end.is_win());
+        let mut reply = Markdown::new().paragraph(
+            Text::new()
+                .raw(&format!("📅  每日 Wordle #{} ", self.number))
+                .mention(identity)
+                .plain(&format!(" {}", game.score())),
+        );
+        // colours only, the letters would give the answer away to the others
+        reply = reply.raw(&game.grid());
+        reply = match over {
+            Some(_) if won => reply.paragraph(" 恭喜猜中，小鱼干奉上🎉 谜底将在今日结束时公布"),
+            Some(_) => reply.paragraph(" 今天的机会用完了，谜底将在今日结束时公布，明天再来💪"),
+            None => reply,
+        };
+        if over.is_some() {
+            let outcome = if won { Outcome::Won } else { Outcome::Lost };
+            stats.record(&vec![identity.to_string(); game.guesses().count()], outcome);
+        }
+        let reply = reply.to_string();
+        self.save();
+        Ok((reply, won))
+    }
+
+    /// Start the puzzle of `today` if the current one is older, announcing the results of the
+    /// old one if anybody played.
+    ///
+    /// Unfinished games are recorded in `stats` as lost.
+    pub fn roll_over(&mut self, today: usize, stats: &mut Stats) -> Option<String> {
+        if self.number >= today {
+            return None;
+        }
+        let players = std::mem::take(&mut self.players);
+        let old = std::mem::replace(&mut self.number, today);
+        self.save();
+        let solution = players.values().next()?.solution().to_string();
+        info!("daily wordle #{} ends, answer: {}", old, solution);
+
+        let mut reply = Markdown::new().heading(
+            2,
+            Text::new()
+                .plain(&format!("📅  每日 Wordle #{} 谜底：", old))
+                .bold_italic(&solution),
+        );
+        let mut results: Vec<_> = players.iter().collect();
+        // winners first, by the fewest guesses
+        results.sort_by_key(|(_, game)| match game.game_over() {
+            Some(end) if end.is_win() => game.guesses().count(),
+            _ => usize::MAX,
+        });
+        for (identity, game) in results {
+            if game.game_over().is_none() {
+                let guesses = vec![identity.clone(); game.guesses().count()];
+                stats.record(&guesses, Outcome::Lost);
+            }
+            reply = reply
+                .paragraph(
+                    Text::new()
+                        .mention(identity)
+                        .plain(&format!(" {}", game.score())),
+                )
+                .raw(&game.grid());
+        }
+        Some(
+            reply
+                .paragraph(Text::new().raw(&format!(
+                    "🚀  每日 Wordle #{} 开始，回复`/daily guess`参与",
+                    today
+                )))
+                .to_string(),
+        )
+    }
+
+    fn save(&self) {
+        let saved = Saved {
+            number: self.number,
+            guesses: self
+                .players
+                .iter()
+                .map(|(identity, game)| {
+                    let guesses = game.guesses().map(|(word, _)| word.to_string()).collect();
+                    (identity.clone(), guesses)
+                })
+                .collect(),
+        };
+        if let Err(err) = super::save_json(&self.path, &saved) {
+            error!("save {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// File today's games of the wordle game of `thread_id` are kept in.
+pub fn path(data_dir: &Path, thread_id: u64) -> PathBuf {
+    data_dir.join(format!("wordle-{}-daily.json", thread_id))
+}
@@ -0,0 +1,289 @@
+use super::words::WordList;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+
+// puzzle numbers random games are picked from
+const RANDOM_NUMBERS: usize = 100_000;
+
+/// Guesses a game of `len` letters allows, 6 for the usual 5 letters.
+pub fn attempts(len: usize) -> usize {
+    len + 1
+}
+
+/// How a letter of a guess matches the solution.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Match {
+    /// In the solution at this place.
+    Exact,
+    /// In the solution at another place.
+    Close,
+    /// Not in the solution, or not as often.
+    Wrong,
+}
+
+/// How every letter of a guess matches the solution, shown as coloured squares.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Matches(pub Vec<Match>);
+
+impl Display for Matches {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for m in &self.0 {
+            let square = match m {
+                Match::Exact => "🟩",
+                Match::Close => "🟨",
+                Match::Wrong => "⬛",
+            };
+            write!(f, "{}", square)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare `guess` with `solution` of the same length.
+///
+/// A letter guessed more often than it is in the solution is [`Match::Close`] only as many
+/// times as it is left over after the exact matches.
+pub fn diff(guess: &str, solution: &str) -> Matches {
+    let guess: Vec<char> = guess.chars().collect();
+    let solution: Vec<char> = solution.chars().collect();
+    diff_by(&guess, &solution)
+}
+
+/// Compare the parts of `guess` with those of `solution` like [`diff`] compares letters.
+pub fn diff_by<T: PartialEq>(guess: &[T], solution: &[T]) -> Matches {
+    let mut matches = vec![Match::Wrong; guess.len()];
+    let mut left = vec![];
+    for (i, part) in solution.iter().enumerate() {
+        if guess.get(i) == Some(part) {
+            matches[i] = Match::Exact;
+        } else {
+            left.push(part);
+        }
+    }
+    for (i, part) in guess.iter().enumerate() {
+        if matches[i] == Match::Exact {
+            continue;
+        }
+        if let Some(pos) = left.iter().position(|p| *p == part) {
+            left.swap_remove(pos);
+            matches[i] = Match::Close;
+        }
+    }
+    Matches(matches)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOver {
+    /// Solved with this many guesses.
+    Win(usize),
+    Lose,
+}
+
+impl GameOver {
+    pub fn is_win(&self) -> bool {
+        matches!(self, GameOver::Win(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuessError {
+    /// Not as long as the solution.
+    Length(usize),
+    /// Not in the word list.
+    Unknown,
+    GameOver,
+}
+
+/// One game with a solution from a [`WordList`].
+#[derive(Debug, Clone)]
+pub struct Game {
+    number: usize,
+    solution: String,
+    guesses: Vec<(String, Matches)>,
+    words: Arc<WordList>,
+}
+
+impl Game {
+    /// Game with a random solution of `len` letters from `words`, which must have such words.
+    pub fn random(words: Arc<WordList>, len: usize) -> Self {
+        let number = rand::thread_rng().gen_range(0..RANDOM_NUMBERS);
+        Game::new(number, words.solution(number, len), words)
+    }
+
+    /// Game with the solution of the day `day` of `words`, of the default length of the list.
+    pub fn from_day(day: usize, words: Arc<WordList>) -> Self {
+        Game::new(day, words.solution(day, words.default_len()), words)
+    }
+
+    fn new(number: usize, solution: String, words: Arc<WordList>) -> Self {
+        Game {
+            number,
+            solution,
+            guesses: vec![],
+            words,
+        }
+    }
+
+    /// Puzzle number the solution was picked by, the day for daily puzzles.
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn solution(&self) -> &str {
+        &self.solution
+    }
+
+    /// Letters of the solution.
+    pub fn len(&self) -> usize {
+        self.solution.chars().count()
+    }
+
+    /// Guesses the game allows.
+    pub fn attempts(&self) -> usize {
+        attempts(self.len())
+    }
+
+    pub fn words(&self) -> &WordList {
+        &self.words
+    }
+
+    /// Play `guess`, which must be a word of the list as long as the solution.
+    pub fn guess(&mut self, guess: &str) -> Result<Matches, GuessError> {
+        if self.game_over().is_some() {
+            return Err(GuessError::GameOver);
+        }
+        if guess.chars().count() != self.len() {
+            return Err(GuessError::Length(self.len()));
+        }
+        if guess != self.solution && !self.words.is_allowed(guess) {
+            return Err(GuessError::Unknown);
+        }
+        let matches = diff(guess, &self.solution);
+        self.guesses.push((guess.to_string(), matches.clone()));
+        Ok(matches)
+    }
+
+    /// Every guess so far with how it matched.
+    pub fn guesses(&self) -> impl Iterator<Item = (&str, &Matches)> + '_ {
+        self.guesses
+            .iter()
+            .map(|(guess, matches)| (guess.as_str(), matches))
+    }
+
+    pub fn game_over(&self) -> Option<GameOver> {
+        match self.guesses.last() {
+            Some((guess, _)) if *guess == self.solution => Some(GameOver::Win(self.guesses.len())),
+            _ if self.guesses.len() >= self.attempts() => Some(GameOver::Lose),
+            _ => None,
+        }
+    }
+
+    /// `3/6` for a game won in three of six guesses, `X/6` for a lost one.
+    pub fn score(&self) -> String {
+        match self.game_over() {
+            Some(end) if !end.is_win() => format!("X/{}", self.attempts()),
+            _ => format!("{}/{}", self.guesses.len(), self.attempts()),
+        }
+    }
+
+    /// Coloured squares of every guess, one paragraph per guess.
+    pub fn grid(&self) -> String {
+        self.guesses()
+            .map(|(_, matches)| matches.to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Match::{Close, Exact, Wrong};
+
+    fn game(solution: &str, allowed: &[&str]) -> Game {
+        Game::from_day(0, Arc::new(WordList::from_words(&[solution], allowed)))
+    }
+
+    #[test]
+    fn diff_marks_every_letter() {
+        assert_eq!(diff("leant", "leant").0, vec![Exact; 5]);
+        assert_eq!(diff("crisp", "leant").0, vec![Wrong; 5]);
+        assert_eq!(
+            diff("tales", "leant").0,
+            vec![Close, Close, Close, Close, Wrong]
+        );
+    }
+
+    #[test]
+    fn diff_counts_duplicate_letters() {
+        // one e in the solution: the first e of the guess takes it
+        assert_eq!(
+            diff("erase", "leant").0,
+            vec![Close, Wrong, Exact, Wrong, Wrong]
+        );
+        // an exact match takes the letter before a close one
+        assert_eq!(
+            diff("geese", "those").0,
+            vec![Wrong, Wrong, Wrong, Exact, Exact]
+        );
+        // two e in the solution, three in the guess
+        assert_eq!(
+            diff("geese", "eerie").0,
+            vec![Wrong, Exact, Close, Wrong, Exact]
+        );
+    }
+
+    #[test]
+    fn diff_by_compares_any_parts() {
+        assert_eq!(
+            diff_by(&[1, 2, 2, 3], &[2, 1, 4, 3]).0,
+            vec![Close, Close, Wrong, Exact]
+        );
+    }
+
+    #[test]
+    fn matches_show_as_squares() {
+        assert_eq!(diff("erase", "leant").to_string(), "🟨⬛🟩⬛⬛");
+    }
+
+    #[test]
+    fn guess_checks_length_and_list() {
+        let mut game = game("leant", &["tales"]);
+        assert_eq!(game.guess("lean"), Err(GuessError::Length(5)));
+        assert_eq!(game.guess("zzzzz"), Err(GuessError::Unknown));
+        assert_eq!(game.guesses().count(), 0);
+        assert!(game.guess("tales").is_ok());
+        assert_eq!(game.guesses().count(), 1);
+    }
+
+    #[test]
+    fn game_is_won_by_the_solution() {
+        let mut game = game("leant", &["tales"]);
+        game.guess("tales").unwrap();
+        assert_eq!(game.game_over(), None);
+        assert_eq!(game.guess("leant").unwrap().0, vec![Exact; 5]);
+        assert_eq!(game.game_over(), Some(GameOver::Win(2)));
+        assert_eq!(game.score(), "2/6");
+        assert_eq!(game.grid(), "🟨🟨🟨🟨⬛\n\n🟩🟩🟩🟩🟩");
+        assert_eq!(game.guess("tales"), Err(GuessError::GameOver));
+    }
+
+    #[test]
+    fn game_is_lost_after_every_attempt() {
+        let mut game = game("leant", &["tales"]);
+        for _ in 0..game.attempts() {
+            game.guess("tales").unwrap();
+        }
+        assert_eq!(game.game_over(), Some(GameOver::Lose));
+        assert_eq!(game.score(), "X/6");
+        assert_eq!(game.guess("leant"), Err(GuessError::GameOver));
+    }
+
+    #[test]
+    fn attempts_follow_the_length() {
+        let game = game("planets", &[]);
+        assert_eq!(game.len(), 7);
+        assert_eq!(game.attempts(), 8);
+    }
+}
@@ -0,0 +1,1194 @@
+mod daily;
+pub(crate) mod game;
+mod stats;
+pub mod words;
+
+use crate::bot::{Handler, Response};
+use crate::play::{self, Idle};
+use crate::schedule::Schedule;
+use crate::settings::Settings;
+use daily::Daily;
+use game::{Game, GuessError, Match};
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use stats::{Outcome, Stats};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+use words::{Lists, WordList};
+use ykst_client::markdown::{Markdown, Text};
+use ykst_client::model::Post;
+
+enum Action {
+    Nop,
+    /// Start the co-op game.
+    Start(Mode),
+    Guess(String),
+    /// Start the poster's own game.
+    StartSolo(Mode),
+    /// Guess in the poster's own game.
+    Solo(String),
+    /// Guess in the daily puzzle, or show how it goes without a guess.
+    Daily(Option<String>),
+    /// Statistics of the given identity, or of the poster.
+    Stats(Option<String>),
+    Top,
+    /// Reveal a letter of the co-op game, or of the poster's own game if true.
+    Hint(bool),
+    /// Show the result of a finished game without its letters.
+    Share(Shared),
+}
+
+/// Game `/share` shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shared {
+    /// The last co-op game.
+    Coop,
+    /// The poster's last own game.
+    Solo,
+    /// The poster's daily puzzle.
+    Daily,
+}
+
+#[derive(Debug, Clone)]
+enum ParseActionError {
+    InvalidWord(String),
+    EmptyWord,
+    UnsupportedMode(String),
+    UnsupportedLength(String),
+    UnsupportedHint(String),
+    UnsupportedShare(String),
+    UnsupportedAction(String),
+}
+
+// https://fettblog.eu/rust-enums-wrapping-errors/
+impl Display for ParseActionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseActionError::InvalidWord(word) => {
+                write!(
+                    f,
+                    "❌  {} 为无效词汇，请确保单词为{}到{}个英文字母组成",
+                    Text::new().code(word),
+                    words::MIN_LEN,
+                    words::MAX_LEN
+                )
+            }
+            ParseActionError::EmptyWord => {
+                write!(f, "❌  猜测单词为空，请输入英文单词")
+            }
+            ParseActionError::UnsupportedMode(mode) => write!(
+                f,
+                "❌  {} 为不支持的选项，请输入`/start [hard] [长度] [词库]`或`/solo [hard] [长度] [词库]`",
+                Text::new().code(mode)
+            ),
+            ParseActionError::UnsupportedLength(len) => write!(
+                f,
+                "❌  不支持{}个字母的单词，长度须为{}到{}",
+                len,
+                words::MIN_LEN,
+                words::MAX_LEN
+            ),
+            ParseActionError::UnsupportedHint(option) => write!(
+                f,
+                "❌  {} 为不支持的选项，请输入`/hint`或`/hint solo`",
+                Text::new().code(option)
+            ),
+            ParseActionError::UnsupportedShare(option) => write!(
+                f,
+                "❌  {} 为不支持的选项，请输入`/share`、`/share solo`或`/share daily`",
+                Text::new().code(option)
+            ),
+            ParseActionError::UnsupportedAction(action) => write!(
+                f,
+                "❌  {} 为不支持的动作，请输入`/start [hard] [长度] [词库]`、`/guess guess`、`/solo [hard] [长度] [词库]`、`/solo guess`、`/daily [guess]`、`/hint [solo]`、`/share [solo|daily]`、`/stats [@身份]`或`/top`",
+                Text::new().code(action)
+            ),
+        }
+    }
+}
+
+impl Error for ParseActionError {}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Nop => write!(f, "nop"),
+            Action::Guess(guess) => write!(f, "/guess {}", guess),
+            Action::Start(mode) => write!(f, "/start{}", mode),
+            Action::StartSolo(mode) => write!(f, "/solo{}", mode),
+            Action::Solo(guess) => write!(f, "/solo {}", guess),
+            Action::Daily(Some(guess)) => write!(f, "/daily {}", guess),
+            Action::Daily(None) => write!(f, "/daily"),
+            Action::Stats(Some(identity)) => write!(f, "/stats @{}", identity),
+            Action::Stats(None) => write!(f, "/stats"),
+            Action::Top => write!(f, "/top"),
+            Action::Hint(false) => write!(f, "/hint"),
+            Action::Hint(true) => write!(f, "/hint solo"),
+            Action::Share(Shared::Coop) => write!(f, "/share"),
+            Action::Share(Shared::Solo) => write!(f, "/share solo"),
+            Action::Share(Shared::Daily) => write!(f, "/share daily"),
+        }
+    }
+}
+
+/// Options of `/start` and `/solo`.
+#[derive(Debug, Clone, Default)]
+struct Mode {
+    hard: bool,
+    /// Letters of the solution, the default length of the list if not given.
+    len: Option<usize>,
+    /// Name of the word list, the default one if not given.
+    list: Option<String>,
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.hard {
+            write!(f, " hard")?;
+        }
+        if let Some(len) = self.len {
+            write!(f, " {}", len)?;
+        }
+        if let Some(list) = &self.list {
+            write!(f, " {}", list)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct Alphabet(pub [Match; 26]);
+
+impl Display for Alphabet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut row = Text::new();
+        for (i, m) in self.0.iter().enumerate() {
+            if i > 0 && i % 7 == 0 {
+                write!(f, "{}\n\n", row)?;
+                row = Text::new();
+            }
+            let ch = ((i as u8 + b'A') as char).to_string();
+            row = match m {
+                Match::Wrong => row.strikethrough(&ch),
+                Match::Close => row.plain(&ch),
+                Match::Exact => row.bold_italic(&ch),
+            }
+            .plain(" ");
+        }
+        write!(f, "{}", row)
+    }
+}
+
+/// What `/hint` revealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hint {
+    /// A letter in the solution.
+    Letter(char),
+    /// The letter at a place of the solution.
+    Place(usize, char),
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let letter = |ch: char| Text::new().bold_italic(&ch.to_ascii_uppercase().to_string());
+        match *self {
+            Hint::Letter(ch) => write!(f, "谜底中有字母 {}", letter(ch)),
+            Hint::Place(i, ch) => write!(f, "第{}个字母是 {}", i + 1, letter(ch)),
+        }
+    }
+}
+
+struct Wordle {
+    game: Game,
+    feedbacks: Vec<Text>,
+    // identity of every guess
+    guessers: Vec<String>,
+    alphabet: Alphabet,
+    // every guess must use the hints of the earlier ones
+    hard: bool,
+    // when the game started or was last guessed in
+    active_at: Instant,
+    // post the game was last played from
+    post_id: Option<u64>,
+    // with the guesses made before each and who asked for it
+    hints: Vec<(Hint, usize, String)>,
+}
+
+impl Wordle {
+    fn new(hard: bool, game: Game) -> Self {
+        Wordle {
+            game,
+            feedbacks: vec![],
+            guessers: vec![],
+            alphabet: Alphabet([Match::Close; 26]),
+            hard,
+            active_at: Instant::now(),
+            post_id: None,
+            hints: vec![],
+        }
+    }
+
+    /// Add every guess and hint so far to `reply`.
+    fn history(&self, mut reply: Markdown) -> Markdown {
+        reply = self.hints_after(reply, 0);
+        for (i, gu) in self.game.guesses().enumerate() {
+            reply = reply.paragraph(
+                Text::new()
+                    .plain(&gu.1.to_string())
+                    .raw(&self.feedbacks[i].to_string()),
+            );
+            reply = self.hints_after(reply, i + 1);
+        }
+        reply
+    }
+
+    /// Add the hints given after `guesses` guesses to `reply`.
+    fn hints_after(&self, mut reply: Markdown, guesses: usize) -> Markdown {
+        for (hint, _, identity) in self.hints.iter().filter(|(_, n, _)| *n == guesses) {
+            reply = reply.paragraph(
+                Text::new()
+                    .raw(&format!("💡  {}", hint))
+                    .plain("    ")
+                    .mention(identity),
+            );
+        }
+        reply
+    }
+
+    /// Reveal a letter of the solution nobody has found yet, or else the letter of a place
+    /// nobody has found yet, for `identity`.
+    fn hint(&mut self, identity: &str) -> Option<Hint> {
+        let solution: Vec<char> = self.game.solution().chars().collect();
+        let mut letters = vec![];
+        let mut places = vec![];
+        for (word, matches) in self.game.guesses() {
+            for (i, (ch, &m)) in word.chars().zip(&matches.0).enumerate() {
+                if m != Match::Wrong {
+                    letters.push(ch);
+                }
+                if m == Match::Exact {
+                    places.push(i);
+                }
+            }
+        }
+        for (hint, _, _) in &self.hints {
+            match *hint {
+                Hint::Letter(ch) => letters.push(ch),
+                Hint::Place(i, ch) => {
+                    letters.push(ch);
+                    places.push(i);
+                }
+            }
+        }
+        let unknown_letters: Vec<Hint> = solution
+            .iter()
+            .filter(|ch| !letters.contains(ch))
+            .map(|&ch| Hint::Letter(ch))
+            .collect();
+        let unknown_places: Vec<Hint> = (0..solution.len())
+            .filter(|i| !places.contains(i))
+            .map(|i| Hint::Place(i, solution[i]))
+            .collect();
+        let candidates = if unknown_letters.is_empty() {
+            unknown_places
+        } else {
+            unknown_letters
+        };
+        let hint = *candidates.choose(&mut rand::thread_rng())?;
+        let ch = match hint {
+            Hint::Letter(ch) | Hint::Place(_, ch) => ch,
+        };
+        self.alphabet.0[ch as usize - 'a' as usize] = Match::Exact;
+        self.hints
+            .push((hint, self.feedbacks.len(), identity.to_string()));
+        self.active_at = Instant::now();
+        Some(hint)
+    }
+
+    /// Reveal the solution of a game nobody guessed in for `idle`, recording it as abandoned.
+    fn abandon(&self, idle: Idle, stats: &mut Stats) -> String {
+        if !self.guessers.is_empty() {
+            stats.record(&self.guessers, Outcome::Abandoned);
+        }
+        let reply = Markdown::new().heading(
+            2,
+            format!(
+                "{} {}/{}",
+                self.game.solution(),
+                self.feedbacks.len(),
+                self.game.attempts()
+            ),
+        );
+        self.history(reply)
+            .paragraph(format!(
+                " ⌛  {}分钟无人猜词，游戏自动结束，可以开始新游戏了",
+                idle.minutes()
+            ))
+            .to_string()
+    }
+
+    /// Check `guess` against the hard mode rules: letters found in place stay in place, and
+    /// letters found elsewhere are used again.
+    ///
+    /// A guess not as long as the solution is refused before the rules are checked.
+    fn check_hard(&self, guess: &str) -> Result<(), String> {
+        let letters: Vec<char> = guess.chars().collect();
+        if letters.len() != self.game.len() {
+            let err = GuessError::Length(self.game.len());
+            return Err(guess_error(&err, guess, self.game.words()));
+        }
+        let hint = |n: usize, word: &str, colours: &str| {
+            format!(
+                "（第{}次猜测 {} 中为{}）",
+                n + 1,
+                Text::new().code(word),
+                colours
+            )
+        };
+        let letter = |ch: char| Text::new().bold_italic(&ch.to_ascii_uppercase().to_string());
+        for (n, (word, matches)) in self.game.guesses().enumerate() {
+            for (i, (ch, &m)) in word.chars().zip(&matches.0).enumerate() {
+                if m == Match::Exact && letters[i] != ch {
+                    return Err(format!(
+                        "❌  困难模式：第{}个字母必须是 {}{}",
+                        i + 1,
+                        letter(ch),
+                        hint(n, word, "🟩")
+                    ));
+                }
+            }
+        }
+        for (n, (word, matches)) in self.game.guesses().enumerate() {
+            let found: Vec<char> = word
+                .chars()
+                .zip(&matches.0)
+                .filter(|&(_, &m)| m != Match::Wrong)
+                .map(|(ch, _)| ch)
+                .collect();
+            for &ch in &found {
+                // a letter found twice must be used twice
+                let needed = found.iter().filter(|&&c| c == ch).count();
+                let used = letters.iter().filter(|&&c| c == ch).count();
+                if used < needed {
+                    return Err(format!(
+                        "❌  困难模式：必须包含{}个字母 {}{}",
+                        needed,
+                        letter(ch),
+                        hint(n, word, "🟩或🟨")
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Wordle {
+    /// Play `guess` of `identity` from the post `post_id`, returning the feedback, the other
+    /// responses and whether the game is over.
+    ///
+    /// Finished games are recorded in `stats`.
+    fn play(
+        &mut self,
+        identity: &str,
+        post_id: u64,
+        guess: &str,
+        stats: &mut Stats,
+    ) -> Result<(String, Vec<Response>, bool), String> {
+        let mut responses = vec![];
+        if self.hard {
+            if let Err(err) = self.check_hard(guess) {
+                info!("guess breaks hard mode");
+                return Err(err);
+            }
+        }
+        // validate guess
+        let matches = match self.game.guess(guess) {
+            Ok(matches) => matches,
+            Err(err) => {
+                info!("invalid guess");
+                return Err(guess_error(&err, guess, self.game.words()));
+            }
+        };
+        // only valid guesses keep the game from ending idle
+        self.active_at = Instant::now();
+        self.post_id = Some(post_id);
+        let mut feedback = Text::new();
+        for (i, ch) in guess.chars().enumerate() {
+            feedback = feedback.plain(" ");
+            let letter = ch.to_string();
+            match &matches.0[i] {
+                Match::Exact => {
+                    feedback = feedback.bold_italic(&letter);
+                    self.alphabet.0[ch as usize - 'a' as usize] = Match::Exact;
+                }
+                Match::Close => {
+                    feedback = feedback.plain(&letter);
+                    self.alphabet.0[ch as usize - 'a' as usize] = Match::Exact;
+                }
+                Match::Wrong => {
+                    feedback = feedback.strikethrough(&letter);
+                    if self.alphabet.0[ch as usize - 'a' as usize] == Match::Close {
+                        // When the answer is leant, and the guess is erase, the first e is Close and second `e` is Wrong
+                        self.alphabet.0[ch as usize - 'a' as usize] = Match::Wrong;
+                    }
+                }
+            }
+        }
+        feedback = feedback.plain("    ").mention(identity);
+        self.feedbacks.push(feedback); // add feedback to feedbacks
+        self.guessers.push(identity.to_string());
+
+        let over = self.game.game_over();
+        let mut reply = Markdown::new();
+        if over.is_some() {
+            reply = reply.heading(
+                2,
+                format!(
+                    "{} {}/{}",
+                    self.game.solution(),
+                    self.feedbacks.len(),
+                    self.game.attempts()
+                ),
+            );
+        }
+        // show all history guesses
+        reply = self.history(reply);
+        if let Some(end) = &over {
+            if end.is_win() {
+                info!("game ends, win");
+                reply = reply.paragraph(format!(" 恭喜{}，小鱼干奉上🎉", identity));
+                responses.push(Response::Appreciate { post_id, amount: 1 });
+            } else {
+                info!("game ends, lose");
+                reply = reply.paragraph(" 游戏结束，再接再厉💪");
+            }
+            let outcome = if end.is_win() {
+                Outcome::Won
+            } else {
+                Outcome::Lost
+            };
+            stats.record(&self.guessers, outcome);
+            reply = reply.rule().raw(&share(&self.game, self.hard));
+        } else {
+            // print alphabet
+            reply = reply.rule().raw(&self.alphabet.to_string());
+        }
+        Ok((reply.to_string(), responses, over.is_some()))
+    }
+}
+
+// https://qubyte.codes/blog/parsing-input-from-stdin-to-structures-in-rust
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = match play::command(s) {
+            Some(command) => command,
+            None => return Ok(Action::Nop),
+        };
+        let action = match name {
+            "/start" => Action::Start(parse_mode(&args)?),
+            "/stats" => {
+                let identity = args
+                    .first()
+                    .map(|identity| identity.trim_start_matches('@'));
+                Action::Stats(identity.map(String::from))
+            }
+            "/top" => Action::Top,
+            "/share" => match args.first() {
+                None => Action::Share(Shared::Coop),
+                Some(&"solo") => Action::Share(Shared::Solo),
+                Some(&"daily") => Action::Share(Shared::Daily),
+                Some(option) => return Err(ParseActionError::UnsupportedShare(option.to_string())),
+            },
+            "/hint" => match args.first() {
+                None => Action::Hint(false),
+                Some(&"solo") => Action::Hint(true),
+                Some(option) => return Err(ParseActionError::UnsupportedHint(option.to_string())),
+            },
+            "/guess" => match args.first() {
+                Some(guess) => Action::Guess(parse_word(guess)?),
+                None => return Err(ParseActionError::EmptyWord),
+            },
+            "/solo" => match args[..] {
+                // or a list, told apart when handled
+                [guess] if guess != "hard" && parse_word(guess).is_ok() => {
+                    Action::Solo(parse_word(guess)?)
+                }
+                _ => Action::StartSolo(parse_mode(&args)?),
+            },
+            "/daily" => {
+                let guess = args.first().map(|guess| parse_word(guess)).transpose()?;
+                Action::Daily(guess)
+            }
+            _ => return Err(ParseActionError::UnsupportedAction(name.to_string())),
+        };
+        Ok(action)
+    }
+}
+
+/// Options after `/start` or `/solo`: `hard`, the length of the solution and the name of a
+/// list, in any order.
+fn parse_mode(options: &[&str]) -> Result<Mode, ParseActionError> {
+    let mut mode = Mode::default();
+    for &option in options {
+        match option {
+            "hard" if !mode.hard => mode.hard = true,
+            _ if mode.len.is_none() && option.chars().all(|ch| ch.is_ascii_digit()) => match option
+                .parse()
+            {
+                Ok(len) if (words::MIN_LEN..=words::MAX_LEN).contains(&len) => mode.len = Some(len),
+                _ => return Err(ParseActionError::UnsupportedLength(option.to_string())),
+            },
+            _ if mode.list.is_none() && option != "hard" => mode.list = Some(option.to_string()),
+            _ => return Err(ParseActionError::UnsupportedMode(option.to_string())),
+        }
+    }
+    Ok(mode)
+}
+
+fn parse_word(guess: &str) -> Result<String, ParseActionError> {
+    let len = guess.chars().count();
+    if !((words::MIN_LEN..=words::MAX_LEN).contains(&len)
+        && guess.chars().all(|ch| ch.is_ascii_alphabetic()))
+    {
+        return Err(ParseActionError::InvalidWord(guess.to_string()));
+    }
+    Ok(guess.to_lowercase())
+}
+
+/// Reply to a guess the game refused.
+fn guess_error(err: &GuessError, guess: &str, words: &WordList) -> String {
+    match err {
+        GuessError::Length(len) => format!(
+            "❌  {} 为无效词汇，请确保单词为{}个英文字母组成",
+            Text::new().code(guess),
+            len
+        ),
+        GuessError::Unknown => format!(
+            "❌  {} 不在词库 {} 中，请换一个单词",
+            Text::new().code(guess),
+            Text::new().code(&words.name)
+        ),
+        GuessError::GameOver => String::from("❌  游戏已经结束"),
+    }
+}
+
+/// Result of `game` without its letters, to share: the list, number and score of the game,
+/// with `*` for hard mode, above the coloured squares of its guesses.
+fn share(game: &Game, hard: bool) -> String {
+    let list = &game.words().name;
+    let title = if list == words::BUILTIN {
+        String::from("Wordle")
+    } else {
+        format!("Wordle {}", list)
+    };
+    Markdown::new()
+        .paragraph(format!(
+            "{} #{} {}{}",
+            title,
+            game.number(),
+            game.score(),
+            if hard { "*" } else { "" }
+        ))
+        .raw(&game.grid())
+        .to_string()
+}
+
+/// Rules of hard mode for the start message, if `hard`.
+fn hard_rules(hard: bool) -> &'static str {
+    if hard {
+        "\n\n💀  困难模式：🟩的字母之后必须留在原位，🟨的字母之后必须再次使用"
+    } else {
+        ""
+    }
+}
+
+/// Write `value` to `path` as JSON, creating the directory if needed.
+fn save_json(path: &Path, value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    // write aside first so that a crash never leaves half a file
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Wordle bot of a single thread.
+///
+/// Plays a random co-op game started with `/start`, individual games started with `/solo` by
+/// every player who wants one, and a daily puzzle with the same answer for
+/// everybody, in which every player guesses on their own.
+///
+/// Statistics and the daily puzzle are saved as soon as they change, leaving nothing to save on
+/// shutdown; co-op and individual games are not kept across restarts.
+pub struct WordleBot {
+    wordle: Option<Wordle>,
+    // individual games, by identity
+    solos: HashMap<String, Wordle>,
+    daily: Daily,
+    stats: Stats,
+    // games nobody guesses in for this long are ended
+    idle: Option<Idle>,
+    lists: Lists,
+    // hints a game allows
+    hints: usize,
+    // fish recorded against the player for every hint
+    hint_penalty: u32,
+    // last finished co-op game, and whether it was in hard mode
+    last: Option<(Game, bool)>,
+    // last finished individual games, by identity
+    last_solos: HashMap<String, (Game, bool)>,
+}
+
+impl WordleBot {
+    /// Bot of `thread_id`, keeping its statistics in `DATA_DIR`.
+    pub fn new(thread_id: u64, settings: &Settings) -> Self {
+        let data_dir = &settings.data_dir;
+        let lists = Lists::load(&settings.wordle);
+        WordleBot {
+            idle: Idle::from_minutes(settings.wordle.idle_minutes),
+            wordle: None,
+            solos: HashMap::new(),
+            daily: Daily::load(daily::path(data_dir, thread_id), lists.default()),
+            stats: Stats::load(stats::path(data_dir, thread_id)),
+            lists,
+            hints: settings.wordle.hints,
+            hint_penalty: settings.wordle.hint_penalty,
+            last: None,
+            last_solos: HashMap::new(),
+        }
+    }
+
+    /// Start a game of `mode`, if the list of `mode` has words of its length.
+    fn new_game(&self, mode: &Mode) -> Result<Wordle, Response> {
+        let words = match &mode.list {
+            Some(name) => self.lists.get(name).ok_or_else(|| {
+                info!("unknown list {}", name);
+                let names: Vec<_> = self
+                    .lists
+                    .names()
+                    .map(|name| format!("`{}`", name))
+                    .collect();
+                Response::Error(format!(
+                    "❌  {} 为未知词库，可用词库：{}",
+                    Text::new().code(name),
+                    names.join("、")
+                ))
+            })?,
+            None => self.lists.default(),
+        };
+        let len = mode.len.unwrap_or_else(|| words.default_len());
+        let lengths = words.lengths();
+        if !lengths.contains(&len) {
+            info!("list {} has no words of {} letters", words.name, len);
+            let lengths: Vec<_> = lengths.iter().map(usize::to_string).collect();
+            // the built-in list has words of every length
+            let others: Vec<_> = self
+                .lists
+                .names()
+                .filter(|name| {
+                    self.lists
+                        .get(name)
+                        .is_some_and(|list| list.lengths().contains(&len))
+                })
+                .map(|name| format!("`{}`", name))
+                .collect();
+            return Err(Response::Error(format!(
+                "❌  词库 {} 中没有{}个字母的单词，可选长度：{}。含{}个字母单词的词库：{}",
+                Text::new().code(&words.name),
+                len,
+                lengths.join("、"),
+                len,
+                others.join("、")
+            )));
+        }
+        Ok(Wordle::new(mode.hard, Game::random(words, len)))
+    }
+
+    fn start(&mut self, mode: Mode) -> Response {
+        if self.wordle.is_none() {
+            // start game
+            let w = match self.new_game(&mode) {
+                Ok(w) => w,
+                Err(response) => return response,
+            };
+            info!("game started, mode:{}, answer: {}", mode, w.game.solution());
+            let reply = format!("🚀  Wordle 游戏开始，请输入`/guess guess`猜词，谜底为词库 {} 中的{}位单词，一共{}次机会，首先猜对的用户获胜。{}\n\n每次反馈都包括猜测的历史记录和字母表，历史记录的方格会显示三种颜色，表示猜测和答案的接近程度：\n\n+ 🟩代表该字母正确，对应字母***斜体加粗***\n\n+ 🟨代表谜底里有该字母但位置不对\n\n+ ⬛代表谜底没有该字母，对应字母~~删除~~\n\n字母表中***斜体加粗***代表谜底里有该字母，~~删除~~代表谜底没有该字母", Text::new().code(&w.game.words().name), w.game.len(), w.game.attempts(), hard_rules(mode.hard));
+            self.wordle = Some(w);
+            Response::Reply(reply)
+        } else {
+            // game already started
+            info!("game already started");
+            Response::Error(String::from("❌  游戏已经开始，请输入`/guess guess`猜词"))
+        }
+    }
+
+    /// End the games nobody guessed in for too long.
+    fn end_idle(&mut self) -> Vec<Response> {
+        let idle = match self.idle {
+            Some(idle) => idle,
+            None => return vec![],
+        };
+        let mut responses = vec![];
+        if let Some(w) = self.wordle.take_if(|w| idle.is_over(w.active_at)) {
+            info!("game abandoned, answer: {}", w.game.solution());
+            responses.push(Response::Reply(w.abandon(idle, &mut self.stats)));
+        }
+        let idle_solos: Vec<String> = self
+            .solos
+            .iter()
+            .filter(|(_, w)| idle.is_over(w.active_at))
+            .map(|(identity, _)| identity.clone())
+            .collect();
+        for identity in idle_solos {
+            let w = self.solos.remove(&identity).unwrap();
+            info!(
+                "solo game of {} abandoned, answer: {}",
+                identity,
+                w.game.solution()
+            );
+            let content = w.abandon(idle, &mut self.stats);
+            responses.push(match w.post_id {
+                Some(post_id) => Response::ReplyTo { post_id, content },
+                None => Response::Reply(content),
+            });
+        }
+        responses
+    }
+
+    /// Announce the results of the last daily puzzle if a new day has begun.
+    fn roll_over(&mut self) -> Option<Response> {
+        self.daily
+            .roll_over(daily::today(), &mut self.stats)
+            .map(Response::Reply)
+    }
+
+    fn daily(&mut self, post: &Post, post_id: u64, guess: Option<String>) -> Vec<Response> {
+        let mut responses: Vec<_> = self.roll_over().into_iter().collect();
+        let guess = match guess {
+            Some(guess) => guess,
+            None => {
+                let (players, done) = self.daily.progress();
+                responses.push(Response::Reply(format!(
+                    "📅  每日 Wordle #{}：{}人参与，{}人已完成。谜底为{}位单词，每人每天{}次机会，回复`/daily guess`猜词，反馈只显示颜色，谜底和所有人的结果在今日结束时公布",
+                    self.daily.number(),
+                    players,
+                    done,
+                    self.daily.len(),
+                    game::attempts(self.daily.len())
+                )));
+                return responses;
+            }
+        };
+        match self
+            .daily
+            .guess(&post.identity_code, &guess, &mut self.stats)
+        {
+            Ok((reply, won)) => {
+                if won {
+                    responses.push(Response::Appreciate { post_id, amount: 1 });
+                }
+                responses.push(Response::Reply(reply));
+            }
+            Err(err) => {
+                info!("invalid daily guess");
+                responses.push(Response::Error(err));
+            }
+        }
+        responses
+    }
+
+    fn guess(&mut self, post: &Post, post_id: u64, guess: String) -> Vec<Response> {
+        let w = match self.wordle.as_mut() {
+            Some(w) => w,
+            None => {
+                // game not started
+                info!("game not started");
+                return vec![Response::Error(String::from(
+                    "❌  游戏还未开始，请回复`/start`以开始游戏",
+                ))];
+            }
+        };
+        match w.play(&post.identity_code, post_id, &guess, &mut self.stats) {
+            Ok((reply, mut responses, over)) => {
+                if over {
+                    self.last = self.wordle.take().map(|w| (w.game, w.hard));
+                }
+                responses.push(Response::Reply(reply));
+                responses
+            }
+            Err(err) => vec![Response::Error(err)],
+        }
+    }
+
+    fn start_solo(&mut self, post: &Post, post_id: u64, mode: Mode) -> Response {
+        let identity = &post.identity_code;
+        if self.solos.contains_key(identity) {
+            info!("solo game of {} already started", identity);
+            return Response::Error(String::from(
+                "❌  你的游戏已经开始，请输入`/solo guess`猜词",
+            ));
+        }
+        let mut w = match self.new_game(&mode) {
+            Ok(w) => w,
+            Err(response) => return response,
+        };
+        info!(
+            "solo game of {} started, mode:{}, answer: {}",
+            identity,
+            mode,
+            w.game.solution()
+        );
+        w.post_id = Some(post_id);
+        let content = format!(
+            "🚀  你的 Wordle 游戏开始，请输入`/solo guess`猜词，谜底为词库 {} 中的{}位单词，一共{}次机会，反馈与`/start`的游戏相同。{}",
+            Text::new().code(&w.game.words().name),
+            w.game.len(),
+            w.game.attempts(),
+            hard_rules(mode.hard)
+        );
+        self.solos.insert(identity.clone(), w);
+        Response::ReplyTo { post_id, content }
+    }
+
+    fn solo(&mut self, post: &Post, post_id: u64, guess: String) -> Vec<Response> {
+        let identity = &post.identity_code;
+        let w = match self.solos.get_mut(identity) {
+            Some(w) => w,
+            None if self.lists.get(&guess).is_some() => {
+                // `/solo <list>`
+                let mode = Mode {
+                    list: Some(guess),
+                    ..Mode::default()
+                };
+                return vec![self.start_solo(post, post_id, mode)];
+            }
+            None => {
+                info!("solo game of {} not started", identity);
+                return vec![Response::Error(String::from(
+                    "❌  你的游戏还未开始，请回复`/solo`以开始游戏",
+                ))];
+            }
+        };
+        match w.play(identity, post_id, &guess, &mut self.stats) {
+            Ok((reply, mut responses, over)) => {
+                if over {
+                    let w = self.solos.remove(identity).unwrap();
+                    self.last_solos.insert(identity.clone(), (w.game, w.hard));
+                }
+                responses.push(Response::ReplyTo {
+                    post_id,
+                    content: reply,
+                });
+                responses
+            }
+            Err(err) => vec![Response::Error(err)],
+        }
+    }
+
+    /// Reveal a letter of the co-op game, or of the poster's own game if `solo`.
+    fn hint(&mut self, post: &Post, post_id: u64, solo: bool) -> Response {
+        if self.hints == 0 {
+            info!("hints are off");
+            return Response::Error(String::from("❌  本帖未开启提示"));
+        }
+        let identity = &post.identity_code;
+        let w = if solo {
+            self.solos.get_mut(identity)
+        } else {
+            self.wordle.as_mut()
+        };
+        let w = match w {
+            Some(w) => w,
+            None => {
+                info!("no game to hint");
+                return Response::Error(String::from(if solo {
+                    "❌  你的游戏还未开始，请回复`/solo`以开始游戏"
+                } else {
+                    "❌  游戏还未开始，请回复`/start`以开始游戏"
+                }));
+            }
+        };
+        if w.hints.len() >= self.hints {
+            info!("no hints left");
+            return Response::Error(format!("❌  本局{}次提示已经用完", self.hints));
+        }
+        let hint = match w.hint(identity) {
+            Some(hint) => hint,
+            None => return Response::Error(String::from("❌  谜底已经全部猜出，没有可以提示的了")),
+        };
+        info!("hint for {}: {:?}", identity, hint);
+        self.stats.record_hint(identity, self.hint_penalty);
+        let mut content = format!(
+            "💡  提示：{}（本局已用{}/{}次提示",
+            hint,
+            w.hints.len(),
+            self.hints
+        );
+        if self.hint_penalty > 0 {
+            content += &format!(
+                "，罚没{}小鱼干{}条",
+                Text::new().mention(identity),
+                self.hint_penalty
+            );
+        }
+        content += "）";
+        if solo {
+            w.post_id = Some(post_id);
+            Response::ReplyTo { post_id, content }
+        } else {
+            Response::Reply(content)
+        }
+    }
+
+    /// Reply to `/share` with the result of a finished game.
+    fn share(&self, post: &Post, shared: Shared) -> Response {
+        let identity = &post.identity_code;
+        let game = match shared {
+            Shared::Coop => self.last.as_ref().map(|(game, hard)| (game, *hard)),
+            Shared::Solo => self
+                .last_solos
+                .get(identity)
+                .map(|(game, hard)| (game, *hard)),
+            Shared::Daily => self
+                .daily
+                .game_of(identity)
+                .filter(|game| game.game_over().is_some())
+                .map(|game| (game, false)),
+        };
+        match game {
+            Some((game, hard)) => Response::Reply(share(game, hard)),
+            None => {
+                info!("nothing to share");
+                Response::Error(String::from(match shared {
+                    Shared::Coop => "❌  还没有结束的多人游戏可以分享",
+                    Shared::Solo => "❌  你还没有结束的个人游戏可以分享",
+                    Shared::Daily => "❌  你还没有完成今天的每日 Wordle",
+                }))
+            }
+        }
+    }
+}
+
+impl Handler for WordleBot {
+    fn handle(&mut self, post: &Post) -> Vec<Response> {
+        let post_id: u64;
+        if let Some(model) = &post.model {
+            post_id = model.id;
+        } else {
+            warn!("post.model is none");
+            return vec![];
+        }
+        let action = match post.content.parse::<Action>() {
+            Ok(action) => action,
+            Err(err) => {
+                // failed to parse action
+                info!("failed to parse action");
+                return vec![Response::Error(format!("{}", err))];
+            }
+        };
+        info!("floor: {} action: {}", post.floor, action);
+        match action {
+            Action::Start(mode) => vec![self.start(mode)],
+            Action::StartSolo(mode) => vec![self.start_solo(post, post_id, mode)],
+            Action::Guess(guess) => self.guess(post, post_id, guess),
+            Action::Solo(guess) => self.solo(post, post_id, guess),
+            Action::Daily(guess) => self.daily(post, post_id, guess),
+            Action::Stats(identity) => {
+                let identity = identity.as_deref().unwrap_or(&post.identity_code);
+                vec![Response::Reply(self.stats.render_player(identity))]
+            }
+            Action::Top => vec![Response::Reply(self.stats.render_top())],
+            Action::Hint(solo) => vec![self.hint(post, post_id, solo)],
+            Action::Share(shared) => vec![self.share(post, shared)],
+            Action::Nop => vec![],
+        }
+    }
+
+    fn stop(&mut self) -> Vec<Response> {
+        let mut responses = vec![];
+        if let Some(w) = self.wordle.take() {
+            info!("game stopped by admin");
+            responses.push(Response::Reply(format!(
+                "🛑  游戏已被管理员终止，谜底为 {}",
+                Text::new().bold_italic(w.game.solution())
+            )));
+        }
+        if !self.solos.is_empty() {
+            info!("{} solo games stopped by admin", self.solos.len());
+            let mut solos: Vec<_> = self.solos.drain().collect();
+            solos.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let solutions = solos.iter().map(|(identity, w)| {
+                Text::new()
+                    .mention(identity)
+                    .plain("：")
+                    .bold_italic(w.game.solution())
+            });
+            responses.push(Response::Reply(
+                Markdown::new()
+                    .paragraph("🛑  个人游戏已被管理员终止，谜底为")
+                    .list(solutions)
+                    .to_string(),
+            ));
+        }
+        responses
+    }
+
+    fn status(&self) -> String {
+        let game = match &self.wordle {
+            Some(w) => format!(
+                "🎮  {}游戏进行中（词库 {}），已猜{}/{}次",
+                if w.hard { "困难模式" } else { "" },
+                Text::new().code(&w.game.words().name),
+                w.feedbacks.len(),
+                w.game.attempts()
+            ),
+            None => String::from("💤  当前没有进行中的游戏"),
+        };
+        let (players, done) = self.daily.progress();
+        format!(
+            "{}\n\n👤  {}个个人游戏进行中\n\n📅  每日 Wordle #{}：{}人参与，{}人已完成",
+            game,
+            self.solos.len(),
+            self.daily.number(),
+            players,
+            done
+        )
+    }
+
+    fn schedules(&self) -> Vec<(String, Schedule)> {
+        // results are announced at midnight
+        let mut schedules = vec![(String::from("daily"), "0 0 0 * * *".parse().unwrap())];
+        schedules.extend(self.idle.map(|idle| idle.schedule()));
+        schedules
+    }
+
+    fn on_schedule(&mut self, name: &str) -> Vec<Response> {
+        match name {
+            "daily" => self.roll_over().into_iter().collect(),
+            play::IDLE => self.end_idle(),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // thread of its own for the `n`th test keeping data in the temporary directory
+    fn thread_id(n: u64) -> u64 {
+        u64::from(std::process::id()) * 10 + n
+    }
+
+    fn bot(thread_id: u64) -> WordleBot {
+        let mut settings = Settings::default();
+        settings.data_dir = std::env::temp_dir();
+        WordleBot::new(thread_id, &settings)
+    }
+
+    // hard mode game of `solution` after `guesses`
+    fn hard_game(solution: &str, guesses: &[&str]) -> Wordle {
+        let words = Arc::new(WordList::from_words(&[solution], guesses));
+        let mut w = Wordle::new(true, Game::from_day(0, words));
+        for guess in guesses {
+            w.game.guess(guess).unwrap();
+        }
+        w
+    }
+
+    #[test]
+    fn every_length_can_start() {
+        let bot = bot(thread_id(0));
+        for len in words::MIN_LEN..=words::MAX_LEN {
+            let mode = Mode {
+                hard: false,
+                len: Some(len),
+                list: None,
+            };
+            let w = match bot.new_game(&mode) {
+                Ok(w) => w,
+                Err(_) => panic!("no game of {} letters", len),
+            };
+            assert_eq!(w.game.len(), len);
+            assert_eq!(w.game.solution().len(), len);
+            assert!(w.game.words().is_allowed(w.game.solution()));
+        }
+    }
+
+    #[test]
+    fn invalid_guesses_do_not_delay_idle_end() {
+        let mut bot = bot(thread_id(1));
+        bot.idle = Idle::from_minutes(1);
+        let words = Arc::new(WordList::from_words(&["leant"], &["point"]));
+        let mut w = Wordle::new(true, Game::from_day(0, words));
+        w.play("player", 1, "point", &mut bot.stats).unwrap();
+        w.active_at = Instant::now() - Duration::from_secs(120);
+        bot.wordle = Some(w);
+        let post = Post {
+            identity_code: String::from("player"),
+            ..Post::default()
+        };
+        // too short, not in the list, and breaking hard mode
+        for guess in ["abcd", "zzzzz", "leans"] {
+            let responses = bot.guess(&post, 2, guess.to_string());
+            assert!(matches!(responses[..], [Response::Error(_)]), "{}", guess);
+        }
+        assert_eq!(bot.wordle.as_ref().unwrap().post_id, Some(1));
+        assert_eq!(bot.end_idle().len(), 1);
+        assert!(bot.wordle.is_none());
+        // the abandoned game was recorded
+        fs::remove_file(stats::path(&std::env::temp_dir(), thread_id(1))).unwrap();
+    }
+
+    #[test]
+    fn hard_mode_refuses_other_lengths() {
+        // the last letter is found in place
+        let mut w = hard_game("leant", &["point"]);
+        let err = w.check_hard("abcd").unwrap_err();
+        assert!(err.contains("5个英文字母"), "{}", err);
+        assert!(w.check_hard("abcdefgh").is_err());
+        let mut stats = Stats::default();
+        assert!(w.play("player", 1, "abcd", &mut stats).is_err());
+        assert_eq!(w.game.guesses().count(), 1);
+    }
+
+    #[test]
+    fn hard_mode_keeps_found_letters_in_place() {
+        let w = hard_game("leant", &["point"]);
+        let err = w.check_hard("tends").unwrap_err();
+        assert!(err.contains("第4个字母"), "{}", err);
+        assert!(w.check_hard("giant").is_ok());
+    }
+
+    #[test]
+    fn hard_mode_reuses_found_letters() {
+        // t, a, l and e are found elsewhere
+        let w = hard_game("leant", &["tales"]);
+        let err = w.check_hard("plane").unwrap_err();
+        assert!(err.contains("***T***"), "{}", err);
+        assert!(w.check_hard("leant").is_ok());
+        assert!(w.check_hard("latte").is_ok());
+    }
+
+    #[test]
+    fn hard_mode_counts_letters_found_twice() {
+        // three e are found, two of them in place
+        let w = hard_game("eerie", &["geese"]);
+        let err = w.check_hard("beige").unwrap_err();
+        assert!(err.contains("必须包含3个字母"), "{}", err);
+        assert!(w.check_hard("eerie").is_ok());
+    }
+}
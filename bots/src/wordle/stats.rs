@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ykst_client::markdown::{Markdown, Text};
+
+// players shown by `/top`
+const TOP: usize = 10;
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    /// Nobody guessed for too long.
+    Abandoned,
+}
+
+/// Results of one identity over all games of a thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    /// Games the player guessed in.
+    pub games: u32,
+    pub wins: u32,
+    /// Games which timed out, counted as not won.
+    pub abandoned: u32,
+    /// Guesses made, in won and lost games.
+    pub guesses: u32,
+    /// Games won by the player, by the number of guesses of the game minus one.
+    pub distribution: Vec<u32>,
+    /// Games won in a row among those the player guessed in.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Hints asked for with `/hint`.
+    pub hints: u32,
+    /// Fish the hints cost, see `WORDLE.HINT_PENALTY`.
+    pub penalty: u32,
+}
+
+impl PlayerStats {
+    /// Wins per game played, in percent.
+    pub fn win_rate(&self) -> u32 {
+        (self.wins * 100).checked_div(self.games).unwrap_or(0)
+    }
+}
+
+/// Statistics of every player of a thread, saved as JSON after every game.
+#[derive(Debug, Default)]
+pub struct Stats {
+    path: PathBuf,
+    players: BTreeMap<String, PlayerStats>,
+}
+
+impl Stats {
+    /// Read the statistics saved at `path`, starting over if there are none.
+    pub fn load(path: PathBuf) -> Self {
+        let players = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                error!("{}: {}, starting over", path.display(), err);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Stats { path, players }
+    }
+
+    pub fn get(&self, identity: &str) -> Option<&PlayerStats> {
+        self.players.get(identity)
+    }
+
+    /// Record a finished game with the identity of every guess in order, and save.
+    ///
+    /// A game won was won by the identity of the last guess.
+    pub fn record(&mut self, guesses: &[String], outcome: Outcome) {
+        let winner = guesses.last().filter(|_| outcome == Outcome::Won);
+        let mut players: Vec<&String> = guesses.iter().collect();
+        players.sort();
+        players.dedup();
+        for identity in players {
+            let player = self.players.entry(identity.clone()).or_default();
+            player.games += 1;
+            if outcome == Outcome::Abandoned {
+                player.abandoned += 1;
+            }
+            player.guesses += guesses.iter().filter(|guess| *guess == identity).count() as u32;
+            if Some(identity) == winner {
+                player.wins += 1;
+                player.streak += 1;
+                player.best_streak = player.best_streak.max(player.streak);
+                let i = guesses.len() - 1;
+                if player.distribution.len() <= i {
+                    player.distribution.resize(i + 1, 0);
+                }
+                player.distribution[i] += 1;
+            } else {
+                player.streak = 0;
+            }
+        }
+        if let Err(err) = super::save_json(&self.path, &self.players) {
+            error!("save {}: {}", self.path.display(), err);
+        }
+    }
+
+    /// Record a hint asked for by `identity` costing `penalty` fish, and save.
+    pub fn record_hint(&mut self, identity: &str, penalty: u32) {
+        let player = self.players.entry(identity.to_string()).or_default();
+        player.hints += 1;
+        player.penalty += penalty;
+        if let Err(err) = super::save_json(&self.path, &self.players) {
+            error!("save {}: {}", self.path.display(), err);
+        }
+    }
+
+    /// Reply to `/stats`.
+    pub fn render_player(&self, identity: &str) -> String {
+        let player = match self.get(identity) {
+            Some(player) => player,
+            None => return format!("📊  {} 还没有参与过游戏", Text::new().mention(identity)),
+        };
+        let mut reply = Markdown::new()
+            .paragraph(Text::new().raw("📊  ").mention(identity).plain(" 的战绩"))
+            .table(
+                ["游戏", "胜利", "超时", "胜率", "猜测", "连胜", "最长连胜"],
+                [[
+                    player.games.to_string(),
+                    player.wins.to_string(),
+                    player.abandoned.to_string(),
+                    format!("{}%", player.win_rate()),
+                    player.guesses.to_string(),
+                    player.streak.to_string(),
+                    player.best_streak.to_string(),
+                ]],
+            );
+        if player.wins > 0 {
+            let most = player
+                .distribution
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(1)
+                .max(1);
+            let bars = player.distribution.iter().enumerate().map(|(i, &count)| {
+                // bars of up to 10 blocks
+                let width = (count * 10).div_ceil(most) as usize;
+                Text::new().plain(&format!("{} {} {}", i + 1, "🟩".repeat(width), count))
+            });
+            reply = reply.paragraph("猜中所用次数：").list(bars);
+        }
+        if player.hints > 0 {
+            reply = reply.paragraph(format!(
+                "💡  使用提示{}次，罚没小鱼干{}条",
+                player.hints, player.penalty
+            ));
+        }
+        reply.to_string()
+    }
+
+    /// Reply to `/top`.
+    pub fn render_top(&self) -> String {
+        if self.players.is_empty() {
+            return String::from("🏆  还没有完成的游戏");
+        }
+        let mut players: Vec<_> = self.players.iter().collect();
+        players.sort_by(|(a_id, a), (b_id, b)| {
+            (b.wins, b.win_rate(), b.best_streak)
+                .cmp(&(a.wins, a.win_rate(), a.best_streak))
+                .then(a_id.cmp(b_id))
+        });
+        let rows = players
+            .into_iter()
+            .take(TOP)
+            .enumerate()
+            .map(|(rank, (identity, player))| {
+                vec![
+                    Text::from((rank + 1).to_string()),
+                    Text::new().mention(identity),
+                    Text::from(player.wins.to_string()),
+                    Text::from(player.games.to_string()),
+                    Text::from(format!("{}%", player.win_rate())),
+                    Text::from(player.best_streak.to_string()),
+                ]
+            });
+        Markdown::new()
+            .paragraph("🏆  排行榜")
+            .table(["", "玩家", "胜利", "游戏", "胜率", "最长连胜"], rows)
+            .to_string()
+    }
+}
+
+/// File the statistics of the wordle game of `thread_id` are kept in.
+pub fn path(data_dir: &Path, thread_id: u64) -> PathBuf {
+    data_dir.join(format!("wordle-{}.json", thread_id))
+}
@@ -0,0 +1,296 @@
+use crate::settings::{WordListSettings, WordleSettings};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Name of the built-in list: the one of the New York Times built into cl_wordle for 5 letters,
+/// and the lists bundled in `lists/` for the other lengths.
+pub const BUILTIN: &str = "nytimes";
+/// Shortest words a list may hold.
+pub const MIN_LEN: usize = 4;
+/// Longest words a list may hold.
+pub const MAX_LEN: usize = 8;
+// length of the games not asking for one, and of the list of cl_wordle
+const DEFAULT_LEN: usize = 5;
+
+// solutions and other allowed guesses of the built-in list, by length
+const BUNDLED: [(usize, &str, &str); 4] = [
+    (
+        4,
+        include_str!("lists/solutions-4.txt"),
+        include_str!("lists/allowed-4.txt"),
+    ),
+    (
+        6,
+        include_str!("lists/solutions-6.txt"),
+        include_str!("lists/allowed-6.txt"),
+    ),
+    (
+        7,
+        include_str!("lists/solutions-7.txt"),
+        include_str!("lists/allowed-7.txt"),
+    ),
+    (
+        8,
+        include_str!("lists/solutions-8.txt"),
+        include_str!("lists/allowed-8.txt"),
+    ),
+];
+
+/// Words a game picks its solution from, and words it accepts as guesses.
+#[derive(Debug)]
+pub struct WordList {
+    pub name: String,
+    // by length
+    solutions: BTreeMap<usize, Vec<String>>,
+    allowed: HashSet<String>,
+    // pick the solutions of 5 letters from the list of cl_wordle, for the built-in list
+    builtin_solutions: bool,
+    // accept the guesses of the list of cl_wordle too
+    builtin_allowed: bool,
+}
+
+impl WordList {
+    pub fn builtin() -> Self {
+        let mut solutions = BTreeMap::new();
+        let mut allowed = HashSet::new();
+        for (len, words, others) in BUNDLED {
+            let words = parse_words(words, "built-in list").unwrap();
+            allowed.extend(words.iter().cloned());
+            allowed.extend(parse_words(others, "built-in list").unwrap());
+            solutions.insert(len, words);
+        }
+        WordList {
+            name: String::from(BUILTIN),
+            solutions,
+            allowed,
+            builtin_solutions: true,
+            builtin_allowed: true,
+        }
+    }
+
+    /// Read the list configured in `WORDLE.LISTS`.
+    ///
+    /// Without an `ALLOWED` file, guesses are checked against the built-in list.
+    pub fn load(settings: &WordListSettings) -> Result<Self, String> {
+        if settings.name == BUILTIN {
+            return Err(format!("`{}` is the name of the built-in list", BUILTIN));
+        }
+        let words = read_words(&settings.solutions)?;
+        if words.is_empty() {
+            return Err(format!("{}: no words", settings.solutions.display()));
+        }
+        let mut allowed: HashSet<String> = words.iter().cloned().collect();
+        let mut solutions: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for word in words {
+            solutions
+                .entry(word.chars().count())
+                .or_default()
+                .push(word);
+        }
+        let builtin_allowed = match &settings.allowed {
+            Some(path) => {
+                allowed.extend(read_words(path)?);
+                false
+            }
+            None => {
+                allowed.extend(WordList::builtin().allowed);
+                true
+            }
+        };
+        Ok(WordList {
+            name: settings.name.clone(),
+            solutions,
+            allowed,
+            builtin_solutions: false,
+            builtin_allowed,
+        })
+    }
+
+    /// List of `solutions`, accepting them and `allowed` as guesses.
+    #[cfg(test)]
+    pub fn from_words(solutions: &[&str], allowed: &[&str]) -> Self {
+        let mut by_len: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for word in solutions {
+            by_len.entry(word.len()).or_default().push(word.to_string());
+        }
+        WordList {
+            name: String::from("test"),
+            solutions: by_len,
+            allowed: solutions
+                .iter()
+                .chain(allowed)
+                .map(|word| word.to_string())
+                .collect(),
+            builtin_solutions: false,
+            builtin_allowed: false,
+        }
+    }
+
+    /// Lengths of the solutions of the list.
+    pub fn lengths(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.solutions.keys().copied().collect();
+        if self.builtin_solutions {
+            lengths.push(DEFAULT_LEN);
+            lengths.sort();
+        }
+        lengths
+    }
+
+    /// Length of the games not asking for one: 5 letters if the list has such words, else the
+    /// shortest words of the list.
+    pub fn default_len(&self) -> usize {
+        let lengths = self.lengths();
+        if lengths.contains(&DEFAULT_LEN) {
+            DEFAULT_LEN
+        } else {
+            lengths[0]
+        }
+    }
+
+    /// Solution of `len` letters of the day `day`, the same for everybody.
+    ///
+    /// The list must have words of `len` letters, see [`WordList::lengths`].
+    pub fn solution(&self, day: usize, len: usize) -> String {
+        if self.builtin_solutions && len == DEFAULT_LEN {
+            return cl_wordle::game::Game::from_day(day, cl_wordle::words::NYTIMES)
+                .solution()
+                .to_string();
+        }
+        let solutions = &self.solutions[&len];
+        // jump around the list instead of following its (often alphabetical) order
+        let i = mix(day as u64) % solutions.len() as u64;
+        solutions[i as usize].clone()
+    }
+
+    /// Whether `word` may be guessed.
+    pub fn is_allowed(&self, word: &str) -> bool {
+        self.allowed.contains(word) || (self.builtin_allowed && builtin_allowed(word))
+    }
+}
+
+fn builtin_allowed(word: &str) -> bool {
+    // a fresh game accepts every word of the list
+    cl_wordle::game::Game::from_day(0, cl_wordle::words::NYTIMES)
+        .guess(word)
+        .is_ok()
+}
+
+// splitmix64, a stable stand-in for a hash of the day
+fn mix(n: u64) -> u64 {
+    let mut z = n.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Words of the file at `path`, see [`parse_words`].
+fn read_words(path: &Path) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    parse_words(&content, &path.display().to_string())
+}
+
+/// Words of `content`, read from `source`, one per line; blank lines and lines starting with `#`
+/// are skipped.
+fn parse_words(content: &str, source: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut seen = HashSet::new();
+    for (i, line) in content.lines().enumerate() {
+        let word = line.trim().to_lowercase();
+        if word.is_empty() || word.starts_with('#') {
+            continue;
+        }
+        let len = word.chars().count();
+        if !((MIN_LEN..=MAX_LEN).contains(&len) && word.chars().all(|ch| ch.is_ascii_lowercase())) {
+            return Err(format!(
+                "{}:{}: `{}` is not a word of {} to {} letters",
+                source,
+                i + 1,
+                word,
+                MIN_LEN,
+                MAX_LEN
+            ));
+        }
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+/// The word lists of a bot, by name.
+#[derive(Debug)]
+pub struct Lists {
+    default: Arc<WordList>,
+    lists: BTreeMap<String, Arc<WordList>>,
+}
+
+impl Lists {
+    /// Read the lists of `WORDLE`, leaving out those which can't be read. `--check-config` reports
+    /// them, as does a reload of the config.
+    ///
+    /// Every handler reads them when it is created, so lists changed by a reload of the config
+    /// reach a thread once it is reset.
+    pub fn load(settings: &WordleSettings) -> Self {
+        let mut lists = BTreeMap::new();
+        lists.insert(String::from(BUILTIN), Arc::new(WordList::builtin()));
+        for list in &settings.lists {
+            match WordList::load(list) {
+                Ok(words) => {
+                    lists.insert(list.name.clone(), Arc::new(words));
+                }
+                Err(err) => error!("word list {}: {}", list.name, err),
+            }
+        }
+        let default = lists
+            .get(&settings.default_list)
+            .or_else(|| lists.get(BUILTIN))
+            .cloned()
+            .unwrap();
+        Lists { default, lists }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<WordList>> {
+        self.lists.get(name).cloned()
+    }
+
+    /// List of `DEFAULT_LIST`, used by `/start` without a list and by the daily puzzle.
+    pub fn default(&self) -> Arc<WordList> {
+        self.default.clone()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.lists.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_has_every_length() {
+        let list = WordList::builtin();
+        assert_eq!(list.lengths(), (MIN_LEN..=MAX_LEN).collect::<Vec<_>>());
+        for (len, solutions) in &list.solutions {
+            assert!(!solutions.is_empty());
+            for word in solutions {
+                assert_eq!(word.len(), *len);
+                assert!(list.is_allowed(word), "{}", word);
+            }
+        }
+        assert_eq!(list.solution(0, 4), list.solution(0, 4));
+        assert!(list.is_allowed("crane"));
+        assert!(!list.is_allowed("xqzvj"));
+    }
+
+    #[test]
+    fn parse_skips_comments_and_duplicates() {
+        let words = parse_words("# comment\nWord\n\n  word\nother\n", "test").unwrap();
+        assert_eq!(words, ["word", "other"]);
+        let err = parse_words("word\nab\n", "test").unwrap_err();
+        assert!(err.starts_with("test:2:"), "{}", err);
+        assert!(parse_words("café\n", "test").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 #![allow(dead_code)]
 
 use model::{tree_hole_client::TreeHoleClient, *};
+use std::fmt;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tonic::metadata::{Ascii, MetadataValue};
 use tonic::transport::{Channel, Endpoint};
 use tonic::{
     service::{interceptor::InterceptedService, Interceptor},
     Request, Status,
 };
 
+pub mod markdown;
+
 pub mod model {
     tonic::include_proto!("model");
 }
@@ -35,32 +40,112 @@ pub enum Category {
     Deep,
 }
 
+impl Category {
+    pub const ALL: [Category; 9] = [
+        Category::Main,
+        Category::School,
+        Category::Canteen,
+        Category::Emotion,
+        Category::Study,
+        Category::Tech,
+        Category::DutyRoom,
+        Category::Game,
+        Category::Deep,
+    ];
+
+    pub fn from_id(id: u64) -> Option<Self> {
+        Category::ALL
+            .iter()
+            .copied()
+            .find(|category| *category as u64 == id)
+    }
+
+    /// Lowercase English name, e.g. `duty-room`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Main => "main",
+            Category::School => "school",
+            Category::Canteen => "canteen",
+            Category::Emotion => "emotion",
+            Category::Study => "study",
+            Category::Tech => "tech",
+            Category::DutyRoom => "duty-room",
+            Category::Game => "game",
+            Category::Deep => "deep",
+        }
+    }
+}
+
+impl std::str::FromStr for Category {
+    type Err = String;
+
+    /// Parse the name or the id of a category.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let by_id = s.parse().ok().and_then(Category::from_id);
+        by_id
+            .or_else(|| {
+                Category::ALL
+                    .into_iter()
+                    .find(|category| category.name() == s)
+            })
+            .ok_or_else(|| format!("unknown category `{}`", s))
+    }
+}
+
+/// Whether `token` can be sent in the `authorization` header of a request.
+pub fn is_valid_token(token: &str) -> bool {
+    parse_token(token).is_some()
+}
+
+fn parse_token(token: &str) -> Option<MetadataValue<Ascii>> {
+    token.parse().ok()
+}
+
+#[derive(Clone)]
 pub struct AuthInterceptor {
-    token: String,
+    // shared with the `Client` so the token can be replaced while the channel is in use
+    token: Arc<RwLock<String>>,
 }
 
 impl AuthInterceptor {
     pub fn new(token: String) -> Self {
-        AuthInterceptor { token }
+        AuthInterceptor {
+            token: Arc::new(RwLock::new(token)),
+        }
     }
 }
 
 impl Interceptor for AuthInterceptor {
     fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
         // insert treehole token
-        request
-            .metadata_mut()
-            .insert("authorization", self.token.parse().unwrap());
+        let token = parse_token(&self.token.read().unwrap())
+            .ok_or_else(|| Status::unauthenticated("invalid treehole token"))?;
+        request.metadata_mut().insert("authorization", token);
         Ok(request)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Client<T> {
     client: TreeHoleClient<T>,
     pub identity: String,
+    token: Arc<RwLock<String>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Client<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the token gives access to the account, keep it out of logs
+        f.debug_struct("Client")
+            .field("client", &self.client)
+            .field("identity", &self.identity)
+            .field("token", &"<redacted>")
+            .finish()
+    }
 }
 
+/// Client authenticated with a treehole token, as returned by [`Client::new`].
+pub type AuthClient = Client<InterceptedService<Channel, AuthInterceptor>>;
+
 impl Client<InterceptedService<Channel, AuthInterceptor>> {
     pub async fn new(
         api_url: String,
@@ -76,12 +161,23 @@ impl Client<InterceptedService<Channel, AuthInterceptor>> {
         } else {
             Endpoint::from_shared(api_url)?.connect().await?
         };
-        let client = TreeHoleClient::with_interceptor(channel, AuthInterceptor::new(token));
-        let mut c = Client { client, identity };
+        let interceptor = AuthInterceptor::new(token);
+        let token = interceptor.token.clone();
+        let client = TreeHoleClient::with_interceptor(channel, interceptor);
+        let mut c = Client {
+            client,
+            identity,
+            token,
+        };
         c.ping().await?;
         Ok(c)
     }
 
+    /// Replace the token of this client and of all its clones.
+    pub fn set_token(&self, token: String) {
+        *self.token.write().unwrap() = token;
+    }
+
     pub async fn ping(&mut self) -> Result<EmptyRequest, Box<dyn std::error::Error>> {
         let request = tonic::Request::new(EmptyRequest {});
         let response = self.client.ping(request).await?.into_inner();
@@ -102,6 +198,23 @@ impl Client<InterceptedService<Channel, AuthInterceptor>> {
         Ok(threads)
     }
 
+    /// Latest threads of `category`, `size` at a time, starting after the first `last`.
+    pub async fn get_threads(
+        &mut self,
+        category: Category,
+        last: u64,
+        size: u32,
+    ) -> Result<ThreadsResponse, Box<dyn std::error::Error>> {
+        let request = tonic::Request::new(ThreadsQueryRequest {
+            category_id: category as u64,
+            last,
+            size,
+            ..Default::default()
+        });
+        let threads = self.client.get_threads(request).await?.into_inner();
+        Ok(threads)
+    }
+
     pub async fn create_thread(
         &mut self,
         category: Category,
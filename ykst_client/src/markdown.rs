@@ -0,0 +1,390 @@
+//! Building Markdown replies out of untrusted text.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Longest post the treehole accepts, in characters.
+pub const MAX_POST_LEN: usize = 2000;
+
+// separates blocks, and the places where a long post may be split
+const BLOCK_SEPARATOR: &str = "\n\n";
+
+/// Escape the characters of `text` which have a meaning in Markdown.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`'
+                | '*'
+                | '_'
+                | '~'
+                | '['
+                | ']'
+                | '('
+                | ')'
+                | '#'
+                | '+'
+                | '-'
+                | '|'
+                | '>'
+                | '<'
+                | '!'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Split `text` into posts of at most `max_len` characters.
+///
+/// Posts are split between blocks if possible, then between lines, and only cut inside a line
+/// as a last resort.
+pub fn split(text: &str, max_len: usize) -> Vec<String> {
+    let mut posts = vec![];
+    let mut post = String::new();
+    let mut len = 0;
+    for block in text.split(BLOCK_SEPARATOR) {
+        for piece in fit(block, max_len) {
+            let piece_len = piece.chars().count();
+            if !post.is_empty() && len + BLOCK_SEPARATOR.len() + piece_len > max_len {
+                posts.push(std::mem::take(&mut post));
+                len = 0;
+            }
+            if !post.is_empty() {
+                post.push_str(BLOCK_SEPARATOR);
+                len += BLOCK_SEPARATOR.len();
+            }
+            post.push_str(&piece);
+            len += piece_len;
+        }
+    }
+    if !post.is_empty() || posts.is_empty() {
+        posts.push(post);
+    }
+    posts
+}
+
+/// Cut `block` into pieces of at most `max_len` characters.
+fn fit(block: &str, max_len: usize) -> Vec<String> {
+    if block.chars().count() <= max_len {
+        return vec![block.to_string()];
+    }
+    let mut pieces = vec![];
+    let mut piece = String::new();
+    let mut len = 0;
+    for line in block.split('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        for chunk in chars.chunks(max_len.max(1)) {
+            if !piece.is_empty() && len + 1 + chunk.len() > max_len {
+                pieces.push(std::mem::take(&mut piece));
+                len = 0;
+            }
+            if !piece.is_empty() {
+                piece.push('\n');
+                len += 1;
+            }
+            piece.extend(chunk);
+            len += chunk.len();
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Inline Markdown, e.g. a paragraph or a table cell.
+///
+/// Text added with [`Text::plain`] and the other styles is escaped; only [`Text::raw`] is
+/// copied as it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Text(String);
+
+impl Text {
+    pub fn new() -> Self {
+        Text::default()
+    }
+
+    pub fn plain(mut self, text: &str) -> Self {
+        self.0.push_str(&escape(text));
+        self
+    }
+
+    /// Trusted Markdown, copied without escaping.
+    pub fn raw(mut self, markdown: &str) -> Self {
+        self.0.push_str(markdown);
+        self
+    }
+
+    pub fn bold(self, text: &str) -> Self {
+        self.styled("**", text)
+    }
+
+    pub fn bold_italic(self, text: &str) -> Self {
+        self.styled("***", text)
+    }
+
+    pub fn strikethrough(self, text: &str) -> Self {
+        self.styled("~~", text)
+    }
+
+    /// A code span, fenced with enough backticks to hold the backticks in `text`.
+    pub fn code(mut self, text: &str) -> Self {
+        let mut longest = 0;
+        let mut run = 0;
+        for ch in text.chars() {
+            run = if ch == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat(longest + 1);
+        // a space keeps a backtick at either end from joining the fence
+        let pad = if text.starts_with('`') || text.ends_with('`') {
+            " "
+        } else {
+            ""
+        };
+        self.0
+            .push_str(&format!("{}{}{}{}{}", fence, pad, text, pad, fence));
+        self
+    }
+
+    /// `@identity`, the way users are referred to in a thread.
+    pub fn mention(mut self, identity: &str) -> Self {
+        self.0.push('@');
+        self.0.push_str(&escape(identity));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn styled(mut self, marker: &str, text: &str) -> Self {
+        if !text.is_empty() {
+            self.0.push_str(marker);
+            self.0.push_str(&escape(text));
+            self.0.push_str(marker);
+        }
+        self
+    }
+}
+
+impl Display for Text {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Text::new().plain(text)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Text::new().plain(&text)
+    }
+}
+
+/// A Markdown post made of blocks separated by blank lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Markdown {
+    blocks: Vec<String>,
+}
+
+impl Markdown {
+    pub fn new() -> Self {
+        Markdown::default()
+    }
+
+    /// A heading of `level` 1 to 6.
+    pub fn heading(self, level: usize, text: impl Into<Text>) -> Self {
+        let level = level.clamp(1, 6);
+        self.block(format!("{} {}", "#".repeat(level), text.into()))
+    }
+
+    pub fn paragraph(self, text: impl Into<Text>) -> Self {
+        self.block(text.into().to_string())
+    }
+
+    /// A bullet list, one item per line.
+    pub fn list<I, T>(self, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Text>,
+    {
+        let items: Vec<_> = items
+            .into_iter()
+            .map(|item| format!("+ {}", item.into()))
+            .collect();
+        self.block(items.join("\n"))
+    }
+
+    /// A table with a `header` row; rows shorter than the header are padded with empty cells.
+    pub fn table<H, R, C>(self, header: H, rows: R) -> Self
+    where
+        H: IntoIterator,
+        H::Item: Into<Text>,
+        R: IntoIterator,
+        R::Item: IntoIterator<Item = C>,
+        C: Into<Text>,
+    {
+        let header: Vec<Text> = header.into_iter().map(Into::into).collect();
+        let mut lines = vec![
+            row(&header, header.len()),
+            format!("|{}", " --- |".repeat(header.len())),
+        ];
+        for cells in rows {
+            let cells: Vec<Text> = cells.into_iter().map(Into::into).collect();
+            lines.push(row(&cells, header.len()));
+        }
+        self.block(lines.join("\n"))
+    }
+
+    /// A horizontal rule.
+    pub fn rule(self) -> Self {
+        self.block(String::from("___"))
+    }
+
+    /// Trusted Markdown, added as a block without escaping.
+    pub fn raw(self, markdown: &str) -> Self {
+        self.block(markdown.to_string())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The posts to send, split to fit [`MAX_POST_LEN`].
+    pub fn build(&self) -> Vec<String> {
+        split(&self.to_string(), MAX_POST_LEN)
+    }
+
+    fn block(mut self, block: String) -> Self {
+        self.blocks.push(block);
+        self
+    }
+}
+
+fn row(cells: &[Text], width: usize) -> String {
+    let mut line = String::from("|");
+    for i in 0..width.max(cells.len()) {
+        let cell = cells.get(i).map(Text::to_string).unwrap_or_default();
+        // a table row must fit on one line
+        line.push_str(&format!(" {} |", cell.replace('\n', " ")));
+    }
+    line
+}
+
+impl Display for Markdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.blocks.join(BLOCK_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // blocks, lines, and lines too long for a post, with characters of several bytes
+    fn samples() -> Vec<String> {
+        vec![
+            String::new(),
+            String::from("short"),
+            String::from("第一段\n\n第二段第二段\n第二段\n\n第三段"),
+            "🟩🟨⬛".repeat(40),
+            format!(
+                "{}\n\n{}\n{}\n\n\n\n{}",
+                "a".repeat(25),
+                "b".repeat(7),
+                "汉".repeat(31),
+                "c d ".repeat(9)
+            ),
+        ]
+    }
+
+    #[test]
+    fn split_never_exceeds_max_len() {
+        for text in samples() {
+            for max_len in 1..=40 {
+                for post in split(&text, max_len) {
+                    assert!(
+                        post.chars().count() <= max_len,
+                        "{:?} split at {} gave {:?}",
+                        text,
+                        max_len,
+                        post
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn split_keeps_the_text() {
+        for text in samples() {
+            for max_len in 1..=40 {
+                let joined: String = split(&text, max_len).concat();
+                assert_eq!(
+                    joined.replace('\n', ""),
+                    text.replace('\n', ""),
+                    "split at {}",
+                    max_len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn split_prefers_block_boundaries() {
+        let text = format!("{}\n\n{}", "a".repeat(6), "b".repeat(6));
+        assert_eq!(split(&text, 10), vec!["a".repeat(6), "b".repeat(6)]);
+        assert_eq!(split(&text, 14), vec![text.clone()]);
+        assert_eq!(split("", 10), vec![String::new()]);
+    }
+
+    #[test]
+    fn escape_markdown_characters() {
+        assert_eq!(escape("plain 文本"), "plain 文本");
+        assert_eq!(escape("**a_b**"), "\\*\\*a\\_b\\*\\*");
+        assert_eq!(escape("[x](y)"), "\\[x\\]\\(y\\)");
+        assert_eq!(escape("\\`~#+-|<>!"), "\\\\\\`\\~\\#\\+\\-\\|\\<\\>\\!");
+    }
+
+    #[test]
+    fn text_escapes_all_but_raw() {
+        let text = Text::new()
+            .plain("*a*")
+            .bold("b_")
+            .bold_italic("c")
+            .strikethrough("d")
+            .strikethrough("")
+            .mention("x*y")
+            .raw("**raw**");
+        assert_eq!(text.to_string(), "\\*a\\***b\\_*****c***~~d~~@x\\*y**raw**");
+    }
+
+    #[test]
+    fn code_fences_backticks() {
+        assert_eq!(Text::new().code("a*b").to_string(), "`a*b`");
+        assert_eq!(Text::new().code("a`b").to_string(), "``a`b``");
+        assert_eq!(Text::new().code("`a").to_string(), "`` `a ``");
+    }
+
+    #[test]
+    fn markdown_blocks() {
+        let markdown = Markdown::new()
+            .heading(9, "t")
+            .paragraph("p")
+            .list(["x", "y"])
+            .table(["h1", "h2"], [vec!["a\nb"]])
+            .rule();
+        assert_eq!(
+            markdown.to_string(),
+            "###### t\n\np\n\n+ x\n+ y\n\n| h1 | h2 |\n| --- | --- |\n| a b |  |\n\n___"
+        );
+    }
+}